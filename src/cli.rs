@@ -1,4 +1,8 @@
-use clap::{Arg, Command};
+// Lints raised by the baseline code of this module, which is kept as it was
+#![allow(clippy::needless_borrows_for_generic_args)]
+
+use clap::{Arg, ArgAction, Command};
+use clap::builder::PossibleValue;
 
 pub fn build_cli() -> Command {
     let app = Command::new("pact_cli")
@@ -6,6 +10,7 @@ pub fn build_cli() -> Command {
         .subcommand(
             Command::new("pact-broker")
                 .subcommand(add_publish_pacts_subcommand())
+                .subcommand(add_publish_verification_results_subcommand())
                 .subcommand(add_list_latest_pact_versions_subcommand())
                 .subcommand(add_create_environment_subcommand())
                 .subcommand(add_update_environment_subcommand())
@@ -28,6 +33,8 @@ pub fn build_cli() -> Command {
                 .subcommand(add_create_version_tag_subcommand())
                 .subcommand(add_describe_version_subcommand())
                 .subcommand(add_create_or_update_version_subcommand())
+                .subcommand(add_navigate_subcommand())
+                .subcommand(add_browse_subcommand())
                 .subcommand(add_generate_uuid_subcommand()),
         )
         .subcommand(Command::new("pactflow").subcommand(add_publish_provider_contract_subcommand()))
@@ -75,7 +82,6 @@ fn add_broker_auth_arguments() -> Vec<Arg> {
             .long("broker-base-url")
             .num_args(1)
             .help("The base URL of the Pact Broker")
-            .required(true)
             .value_name("PACT_BROKER_BASE_URL")
             .env("PACT_BROKER_BASE_URL"),
         Arg::new("broker-username")
@@ -99,17 +105,107 @@ fn add_broker_auth_arguments() -> Vec<Arg> {
             .help("Pact Broker bearer token")
             .value_name("PACT_BROKER_TOKEN")
             .env("PACT_BROKER_TOKEN"),
+        Arg::new("profile")
+            .long("profile")
+            .num_args(1)
+            .help("The profile from ~/.config/pact/config.toml to take the Pact Broker URL and credentials from. Arguments and environment variables take precedence over the profile")
+            .value_name("PROFILE")
+            .env("PACT_BROKER_PROFILE"),
+        Arg::new("ssl-cert-file")
+            .long("ssl-cert-file")
+            .num_args(1)
+            .help("PEM file with the CA certificates to trust when connecting to the Pact Broker, in addition to the system certificates")
+            .value_name("SSL_CERT_FILE")
+            .env("PACT_BROKER_SSL_CERT_FILE"),
+        Arg::new("client-cert")
+            .long("client-cert")
+            .num_args(1)
+            .requires("client-key")
+            .help("PEM file with the client certificate to present to the Pact Broker for mutual TLS")
+            .value_name("CLIENT_CERT")
+            .env("PACT_BROKER_CLIENT_CERT"),
+        Arg::new("client-key")
+            .long("client-key")
+            .num_args(1)
+            .requires("client-cert")
+            .help("PEM file with the private key for the client certificate")
+            .value_name("CLIENT_KEY")
+            .env("PACT_BROKER_CLIENT_KEY"),
+        Arg::new("insecure-tls")
+            .long("insecure-tls")
+            .action(ArgAction::SetTrue)
+            .help("Do not verify the TLS certificate of the Pact Broker. Only use this for testing"),
+        Arg::new("max-retries")
+            .long("max-retries")
+            .value_name("MAX_RETRIES")
+            .value_parser(clap::value_parser!(u8))
+            .default_value("3")
+            .help("Maximum number of times to retry a request to the Pact Broker that fails with a connection error, server error or rate limiting")
+            .env("PACT_BROKER_MAX_RETRIES"),
+        Arg::new("retry-backoff")
+            .long("retry-backoff")
+            .value_name("MILLISECONDS")
+            .value_parser(clap::value_parser!(u64))
+            .default_value("200")
+            .help("Delay before the first retry of a request, in milliseconds. The delay doubles for each retry after that, with some random jitter. A Retry-After header from the Pact Broker takes precedence")
+            .env("PACT_BROKER_RETRY_BACKOFF"),
+        Arg::new("timeout")
+            .long("timeout")
+            .value_name("SECONDS")
+            .value_parser(clap::value_parser!(u64).range(1..))
+            .help("Timeout for each request to the Pact Broker, in seconds. There is no timeout by default")
+            .env("PACT_BROKER_TIMEOUT"),
+        Arg::new("offline")
+            .long("offline")
+            .action(ArgAction::SetTrue)
+            .conflicts_with("no-cache")
+            .help("Use the responses cached in ~/.cache/pact/broker from earlier commands without contacting the Pact Broker. Commands that change resources in the Pact Broker fail in offline mode"),
+        Arg::new("no-cache")
+            .long("no-cache")
+            .action(ArgAction::SetTrue)
+            .help("Do not cache the responses from the Pact Broker"),
     ]
 }
-fn add_output_arguments() -> Vec<Arg> {
+fn add_table_output_arguments() -> Vec<Arg> {
     vec![
         Arg::new("output")
         .short('o')
         .long("output")
         .value_name("OUTPUT")
-        .value_parser(clap::builder::PossibleValuesParser::new(&["json", "text"]))
-        .default_value("text")
-        .help("json or text")
+        .value_parser(clap::builder::PossibleValuesParser::new([
+            PossibleValue::new("table"),
+            PossibleValue::new("json"),
+            PossibleValue::new("yaml"),
+            PossibleValue::new("csv"),
+            PossibleValue::new("markdown"),
+            PossibleValue::new("text").hide(true)
+        ]))
+        .default_value("table")
+        .help("table, json, yaml, csv or markdown"),
+        Arg::new("columns")
+        .long("columns")
+        .value_name("COLUMNS")
+        .value_delimiter(',')
+        .action(ArgAction::Append)
+        .help("Comma separated list of the columns to include in the table, csv or markdown output, in order"),
+        Arg::new("no-headers")
+        .long("no-headers")
+        .action(ArgAction::SetTrue)
+        .help("Do not include the header row in the table, csv or markdown output")
+    ]
+}
+fn add_pagination_arguments() -> Vec<Arg> {
+    vec![
+        Arg::new("limit")
+        .long("limit")
+        .value_name("LIMIT")
+        .value_parser(clap::value_parser!(u32).range(1..))
+        .help("Maximum number of items to list. All the items are listed by default"),
+        Arg::new("page-size")
+        .long("page-size")
+        .value_name("PAGE_SIZE")
+        .value_parser(clap::value_parser!(u32).range(1..))
+        .help("Number of items to fetch from the Pact Broker per page")
     ]
 }
 fn add_verbose_arguments() -> Vec<Arg> {
@@ -117,7 +213,7 @@ fn add_verbose_arguments() -> Vec<Arg> {
         Arg::new("verbose")
         .short('v')
         .long("verbose")
-        .num_args(0)
+        .action(ArgAction::SetTrue)
         .help("Verbose output.")
     ]
 }
@@ -127,7 +223,7 @@ fn add_publish_pacts_subcommand() -> Command {
     .args(add_broker_auth_arguments())
     .about("Publishes pacts to the Pact Broker")
     .arg(Arg::new("PACT_DIRS_OR_FILES")
-        .num_args(0..=1)
+        .num_args(1..)
         .required(true)
         .help("Pact directories or files"))
     .arg(Arg::new("consumer-app-version")
@@ -143,17 +239,17 @@ fn add_publish_pacts_subcommand() -> Command {
     .arg(Arg::new("auto-detect-version-properties")
         .short('r')
         .long("auto-detect-version-properties")
-        .num_args(0)
+        .action(ArgAction::SetTrue)
         .help("Automatically detect the repository commit, branch and build URL from known CI environment variables or git CLI. Supports Buildkite, Circle CI, Travis CI, GitHub Actions, Jenkins, Hudson, AppVeyor, GitLab, CodeShip, Bitbucket and Azure DevOps."))
     .arg(Arg::new("tag")
         .short('t')
         .long("tag")
-        .num_args(0..=1)
+        .action(ArgAction::Append)
         .help("Tag name for consumer version. Can be specified multiple times."))
     .arg(Arg::new("tag-with-git-branch")
         .short('g')
         .long("tag-with-git-branch")
-        .num_args(0)
+        .action(ArgAction::SetTrue)
         .help("Tag consumer version with the name of the current git branch. Supports Buildkite, Circle CI, Travis CI, GitHub Actions, Jenkins, Hudson, AppVeyor, GitLab, CodeShip, Bitbucket and Azure DevOps."))
     .arg(Arg::new("build-url")
         .long("build-url")
@@ -161,19 +257,55 @@ fn add_publish_pacts_subcommand() -> Command {
         .help("The build URL that created the pact"))
     .arg(Arg::new("merge")
         .long("merge")
-        .num_args(0)
+        .action(ArgAction::SetTrue)
         .help("If a pact already exists for this consumer version and provider, merge the contents. Useful when running Pact tests concurrently on different build nodes."))
-        .args(add_output_arguments())
+        .args(add_table_output_arguments())
 .args(add_verbose_arguments())
 }
 
+fn add_publish_verification_results_subcommand() -> Command {
+    Command::new("publish-verification-results")
+    .about("Publishes the results of a pact verification from a JSON report written by the Pact verifier (with --json)")
+    .arg(Arg::new("REPORT")
+        .required(true)
+        .help("JSON verification report. The report must only have the results of verifying the pact given with --pact-url"))
+    .arg(Arg::new("pact-url")
+        .long("pact-url")
+        .value_name("PACT_URL")
+        .required(true)
+        .help("URL of the pact in the Pact Broker that was verified"))
+    .arg(Arg::new("provider-app-version")
+        .short('a')
+        .long("provider-app-version")
+        .value_name("PROVIDER_APP_VERSION")
+        .required(true)
+        .value_parser(clap::builder::NonEmptyStringValueParser::new())
+        .help("The provider application version"))
+    .arg(Arg::new("branch")
+        .long("branch")
+        .value_parser(clap::builder::NonEmptyStringValueParser::new())
+        .help("Repository branch of the provider version"))
+    .arg(Arg::new("tag")
+        .short('t')
+        .long("tag")
+        .action(ArgAction::Append)
+        .help("Tag name for provider version. Can be specified multiple times."))
+    .arg(Arg::new("build-url")
+        .long("build-url")
+        .num_args(1)
+        .help("The build URL that verified the pact"))
+    .args(add_table_output_arguments())
+    .args(add_broker_auth_arguments())
+    .args(add_verbose_arguments())
+}
+
 fn add_list_latest_pact_versions_subcommand() -> Command {
     Command::new("list-latest-pact-versions")
         .about("List the latest pact for each integration")
         .args(add_broker_auth_arguments())
         .args(add_verbose_arguments())
-
-        .args(add_output_arguments())
+        .args(add_table_output_arguments())
+        .args(add_pagination_arguments())
 }
 fn add_create_environment_subcommand() -> Command {
     Command::new("create-environment")
@@ -189,7 +321,13 @@ fn add_create_environment_subcommand() -> Command {
         .help("The display name of the environment"))
     .arg(Arg::new("production")
         .long("production")
+        .action(ArgAction::SetTrue)
         .help("Whether or not this environment is a production environment. This is currently informational only."))
+    .arg(Arg::new("no-production")
+        .long("no-production")
+        .action(ArgAction::SetTrue)
+        .conflicts_with("production")
+        .help("This environment is not a production environment"))
     .arg(Arg::new("contact-name")
         .long("contact-name")
         .value_name("CONTACT_NAME")
@@ -198,7 +336,7 @@ fn add_create_environment_subcommand() -> Command {
         .long("contact-email-address")
         .value_name("CONTACT_EMAIL_ADDRESS")
         .help("The email address of the team/person responsible for this environment"))
-        .args(add_output_arguments())
+        .args(add_table_output_arguments())
 
 .args(add_broker_auth_arguments())
 .args(add_verbose_arguments())
@@ -222,7 +360,13 @@ fn add_update_environment_subcommand() -> Command {
         .help("The display name of the environment"))
     .arg(Arg::new("production")
         .long("production")
+        .action(ArgAction::SetTrue)
         .help("Whether or not this environment is a production environment. This is currently informational only."))
+    .arg(Arg::new("no-production")
+        .long("no-production")
+        .action(ArgAction::SetTrue)
+        .conflicts_with("production")
+        .help("This environment is not a production environment"))
     .arg(Arg::new("contact-name")
         .long("contact-name")
         .value_name("CONTACT_NAME")
@@ -231,7 +375,7 @@ fn add_update_environment_subcommand() -> Command {
         .long("contact-email-address")
         .value_name("CONTACT_EMAIL_ADDRESS")
         .help("The email address of the team/person responsible for this environment"))
-        .args(add_output_arguments())
+        .args(add_table_output_arguments())
 .args(add_broker_auth_arguments())
 .args(add_verbose_arguments())
 }
@@ -245,7 +389,14 @@ fn add_delete_environment_subcommand() -> Command {
                 .required(true)
                 .help("The UUID of the environment to delete"),
         )
-        .args(add_output_arguments())
+        .arg(
+            Arg::new("yes")
+                .short('y')
+                .long("yes")
+                .action(ArgAction::SetTrue)
+                .help("Delete the environment without asking for confirmation"),
+        )
+        .args(add_table_output_arguments())
         .args(add_broker_auth_arguments())
 .args(add_verbose_arguments())
 }
@@ -259,14 +410,15 @@ fn add_describe_environment_subcommand() -> Command {
                 .required(true)
                 .help("The UUID of the environment to describe"),
         )
-        .args(add_output_arguments())
+        .args(add_table_output_arguments())
         .args(add_broker_auth_arguments())
 .args(add_verbose_arguments())
 }
 fn add_list_environments_subcommand() -> Command {
     Command::new("list-environments")
         .about("List environments")
-        .args(add_output_arguments())
+        .args(add_table_output_arguments())
+        .args(add_pagination_arguments())
         .args(add_broker_auth_arguments())
 .args(add_verbose_arguments())
 }
@@ -292,6 +444,7 @@ fn add_record_deployment_subcommand() -> Command {
         .help("The name of the environment that the pacticipant version was deployed to"))
     .arg(Arg::new("application-instance")
         .long("application-instance")
+        .alias("target")
         .value_name("APPLICATION_INSTANCE")
         .help("Optional. The application instance to which the deployment has occurred - a logical identifer required to differentiate deployments when there are multiple instances of the same application in an environment. This field was called 'target' in a beta release"))
 
@@ -319,6 +472,7 @@ fn add_record_undeployment_subcommand() -> Command {
     .arg(Arg::new("target")
         .long("target")
         .value_name("TARGET")
+        .conflicts_with("application-instance")
         .help("Optional. The target that the application is being undeployed from - a logical identifer required to differentiate deployments when there are multiple instances of the same application in an environment"))
 
 .args(add_broker_auth_arguments())
@@ -350,7 +504,7 @@ fn add_record_release_subcommand() -> Command {
                 .required(true)
                 .help("The name of the environment that the pacticipant version was released to."),
         )
-        .args(add_output_arguments())
+        .args(add_table_output_arguments())
         .args(add_broker_auth_arguments())
 .args(add_verbose_arguments())
 }
@@ -380,7 +534,7 @@ fn add_record_support_ended_subcommand() -> Command {
                 .required(true)
                 .help("The name of the environment in which the support is ended."),
         )
-        .args(add_output_arguments())
+        .args(add_table_output_arguments())
         .args(add_broker_auth_arguments())
 .args(add_verbose_arguments())
 }
@@ -392,29 +546,37 @@ fn add_can_i_deploy_subcommand() -> Command {
         .long("pacticipant")
         .value_name("PACTICIPANT")
         .required(true)
-        .num_args(0..=1)
+        .action(ArgAction::Append)
         .help("The pacticipant name. Use once for each pacticipant being checked."))
     .arg(Arg::new("version")
         .short('e')
         .long("version")
         .value_name("VERSION")
+        .action(ArgAction::Append)
         .help("The pacticipant version. Must be entered after the --pacticipant that it relates to."))
     .arg(Arg::new("ignore")
         .long("ignore")
         .value_name("PACTICIPANT")
-        .num_args(0..=1)
+        .action(ArgAction::Append)
         .help("The pacticipant name to ignore. Use once for each pacticipant being ignored. A specific version can be ignored by also specifying a --version after the pacticipant name option. The environment variable PACT_BROKER_CAN_I_DEPLOY_IGNORE may also be used to specify a pacticipant name to ignore, with commas to separate multiple pacticipant names if necessary."))
     .arg(Arg::new("latest")
         .short('l')
         .long("latest")
         .value_name("TAG")
+        .action(ArgAction::Append)
+        .num_args(0..=1)
+        .default_missing_value("")
         .help("Use the latest pacticipant version. Optionally specify a TAG to use the latest version with the specified tag."))
     .arg(Arg::new("branch")
         .long("branch")
         .value_name("BRANCH")
+        .action(ArgAction::Append)
         .help("The branch of the version for which you want to check the verification results."))
     .arg(Arg::new("main-branch")
         .long("main-branch")
+        .action(ArgAction::Append)
+        .num_args(0)
+        .default_missing_value("true")
         .help("Use the latest version of the configured main branch of the pacticipant as the version for which you want to check the verification results"))
     .arg(Arg::new("to-environment")
         .long("to-environment")
@@ -424,17 +586,22 @@ fn add_can_i_deploy_subcommand() -> Command {
         .long("to")
         .value_name("TAG")
         .help("The tag that represents the branch or environment of the integrated applications for which you want to check the verification result status."))
-        .args(add_output_arguments())
+        .args(add_table_output_arguments())
     .arg(Arg::new("retry-while-unknown")
         .long("retry-while-unknown")
         .value_name("TIMES")
+        .value_parser(clap::value_parser!(u32))
+        .default_value("0")
         .help("The number of times to retry while there is an unknown verification result (ie. the provider verification is likely still running)"))
     .arg(Arg::new("retry-interval")
         .long("retry-interval")
         .value_name("SECONDS")
+        .value_parser(clap::value_parser!(u64))
+        .default_value("10")
         .help("The time between retries in seconds. Use in conjuction with --retry-while-unknown"))
     .arg(Arg::new("dry-run")
         .long("dry-run")
+        .action(ArgAction::SetTrue)
         .help("When dry-run is enabled, always exit process with a success code. Can also be enabled by setting the environment variable PACT_BROKER_CAN_I_DEPLOY_DRY_RUN=true. This mode is useful when setting up your CI/CD pipeline for the first time, or in a 'break glass' situation where you need to knowingly deploy what Pact considers a breaking change. For the second scenario, it is recommended to use the environment variable and just set it for the build required to deploy that particular version, so you don't accidentally leave the dry run mode enabled."))
 
 .args(add_broker_auth_arguments())
@@ -449,26 +616,30 @@ fn add_can_i_merge_subcommand() -> Command {
         .long("pacticipant")
         .value_name("PACTICIPANT")
         .required(true)
-        .num_args(0..=1)
+        .action(ArgAction::Append)
         .help("The pacticipant name. Use once for each pacticipant being checked."))
     .arg(Arg::new("version")
         .short('e')
         .long("version")
         .value_name("VERSION")
+        .action(ArgAction::Append)
         .help("The pacticipant version. Must be entered after the --pacticipant that it relates to."))
-        .args(add_output_arguments())
+        .args(add_table_output_arguments())
     .arg(Arg::new("retry-while-unknown")
         .long("retry-while-unknown")
         .value_name("TIMES")
+        .value_parser(clap::value_parser!(u32))
         .default_value("0")
         .help("The number of times to retry while there is an unknown verification result (ie. the provider verification is likely still running)"))
     .arg(Arg::new("retry-interval")
         .long("retry-interval")
         .value_name("SECONDS")
+        .value_parser(clap::value_parser!(u64))
         .default_value("10")
         .help("The time between retries in seconds. Use in conjuction with --retry-while-unknown"))
     .arg(Arg::new("dry-run")
         .long("dry-run")
+        .action(ArgAction::SetTrue)
         .help("When dry-run is enabled, always exit process with a success code. Can also be enabled by setting the environment variable PACT_BROKER_CAN_I_MERGE_DRY_RUN=true. This mode is useful when setting up your CI/CD pipeline for the first time, or in a 'break glass' situation where you need to knowingly deploy what Pact considers a breaking change. For the second scenario, it is recommended to use the environment variable and just set it for the build required to deploy that particular version, so you don't accidentally leave the dry run mode enabled."))
 
 .args(add_verbose_arguments())
@@ -502,7 +673,7 @@ fn add_create_or_update_pacticipant_subcommand() -> Command {
                 .value_name("REPOSITORY_URL")
                 .help("The repository URL of the pacticipant"),
         )
-        .args(add_output_arguments())
+        .args(add_table_output_arguments())
 .args(add_verbose_arguments())
 }
 fn add_describe_pacticipant_subcommand() -> Command {
@@ -516,14 +687,15 @@ fn add_describe_pacticipant_subcommand() -> Command {
                 .required(true)
                 .help("Pacticipant name"),
         )
-        .args(add_output_arguments())
+        .args(add_table_output_arguments())
 .args(add_verbose_arguments())
 }
 fn add_list_pacticipants_subcommand() -> Command {
     Command::new("list-pacticipants")
         .about("List pacticipants")
         .args(add_broker_auth_arguments())
-        .args(add_output_arguments())
+        .args(add_table_output_arguments())
+        .args(add_pagination_arguments())
 .args(add_verbose_arguments())
 }
 fn add_create_webhook_subcommand() -> Command {
@@ -537,12 +709,13 @@ fn add_create_webhook_subcommand() -> Command {
         .short('X')
         .long("request")
         .value_name("METHOD")
+        .default_value("POST")
         .help("Webhook HTTP method"))
     .arg(Arg::new("header")
         .short('H')
         .long("header")
         .value_name("one two three")
-        .num_args(0..=1)
+        .action(ArgAction::Append)
         .help("Webhook Header"))
     .arg(Arg::new("data")
         .short('d')
@@ -557,6 +730,7 @@ fn add_create_webhook_subcommand() -> Command {
     .arg(Arg::new("consumer")
         .long("consumer")
         .value_name("CONSUMER")
+        .conflicts_with("consumer-label")
         .help("Consumer name"))
     .arg(Arg::new("consumer-label")
         .long("consumer-label")
@@ -565,6 +739,7 @@ fn add_create_webhook_subcommand() -> Command {
     .arg(Arg::new("provider")
         .long("provider")
         .value_name("PROVIDER")
+        .conflicts_with("provider-label")
         .help("Provider name"))
     .arg(Arg::new("provider-label")
         .long("provider-label")
@@ -576,21 +751,27 @@ fn add_create_webhook_subcommand() -> Command {
         .help("Webhook description"))
     .arg(Arg::new("contract-content-changed")
         .long("contract-content-changed")
+        .action(ArgAction::SetTrue)
         .help("Trigger this webhook when the pact content changes"))
     .arg(Arg::new("contract-published")
         .long("contract-published")
+        .action(ArgAction::SetTrue)
         .help("Trigger this webhook when a pact is published"))
     .arg(Arg::new("provider-verification-published")
         .long("provider-verification-published")
+        .action(ArgAction::SetTrue)
         .help("Trigger this webhook when a provider verification result is published"))
     .arg(Arg::new("provider-verification-failed")
         .long("provider-verification-failed")
+        .action(ArgAction::SetTrue)
         .help("Trigger this webhook when a failed provider verification result is published"))
     .arg(Arg::new("provider-verification-succeeded")
         .long("provider-verification-succeeded")
+        .action(ArgAction::SetTrue)
         .help("Trigger this webhook when a successful provider verification result is published"))
     .arg(Arg::new("contract-requiring-verification-published")
         .long("contract-requiring-verification-published")
+        .action(ArgAction::SetTrue)
         .help("Trigger this webhook when a contract is published that requires verification"))
     .arg(Arg::new("team-uuid")
         .long("team-uuid")
@@ -617,12 +798,13 @@ fn add_create_or_update_webhook_subcommand() -> Command {
         .short('X')
         .long("request")
         .value_name("METHOD")
+        .default_value("POST")
         .help("Webhook HTTP method"))
     .arg(Arg::new("header")
         .short('H')
         .long("header")
         .value_name("one two three")
-        .num_args(0..=1)
+        .action(ArgAction::Append)
         .help("Webhook Header"))
     .arg(Arg::new("data")
         .short('d')
@@ -637,6 +819,7 @@ fn add_create_or_update_webhook_subcommand() -> Command {
     .arg(Arg::new("consumer")
         .long("consumer")
         .value_name("CONSUMER")
+        .conflicts_with("consumer-label")
         .help("Consumer name"))
     .arg(Arg::new("consumer-label")
         .long("consumer-label")
@@ -645,6 +828,7 @@ fn add_create_or_update_webhook_subcommand() -> Command {
     .arg(Arg::new("provider")
         .long("provider")
         .value_name("PROVIDER")
+        .conflicts_with("provider-label")
         .help("Provider name"))
     .arg(Arg::new("provider-label")
         .long("provider-label")
@@ -656,21 +840,27 @@ fn add_create_or_update_webhook_subcommand() -> Command {
         .help("Webhook description"))
     .arg(Arg::new("contract-content-changed")
         .long("contract-content-changed")
+        .action(ArgAction::SetTrue)
         .help("Trigger this webhook when the pact content changes"))
     .arg(Arg::new("contract-published")
         .long("contract-published")
+        .action(ArgAction::SetTrue)
         .help("Trigger this webhook when a pact is published"))
     .arg(Arg::new("provider-verification-published")
         .long("provider-verification-published")
+        .action(ArgAction::SetTrue)
         .help("Trigger this webhook when a provider verification result is published"))
     .arg(Arg::new("provider-verification-failed")
         .long("provider-verification-failed")
+        .action(ArgAction::SetTrue)
         .help("Trigger this webhook when a failed provider verification result is published"))
     .arg(Arg::new("provider-verification-succeeded")
         .long("provider-verification-succeeded")
+        .action(ArgAction::SetTrue)
         .help("Trigger this webhook when a successful provider verification result is published"))
     .arg(Arg::new("contract-requiring-verification-published")
         .long("contract-requiring-verification-published")
+        .action(ArgAction::SetTrue)
         .help("Trigger this webhook when a contract is published that requires verification"))
     .arg(Arg::new("team-uuid")
         .long("team-uuid")
@@ -707,6 +897,11 @@ fn add_delete_branch_subcommand() -> Command {
         .value_name("PACTICIPANT")
         .required(true)
         .help("The name of the pacticipant that the branch belongs to"))
+    .arg(Arg::new("dry-run")
+        .long("dry-run")
+        .action(ArgAction::SetTrue)
+        .help("List the versions on the branch and the pacts that would no longer be selectable through consumer version selectors or WIP pacts, without deleting the branch"))
+    .args(add_table_output_arguments())
 .args(add_verbose_arguments())
 }
 fn add_create_version_tag_subcommand() -> Command {
@@ -733,24 +928,29 @@ fn add_create_version_tag_subcommand() -> Command {
                 .short('t')
                 .long("tag")
                 .value_name("TAG")
-                .num_args(0..=1)
+                .action(ArgAction::Append)
                 .help("Tag name for pacticipant version. Can be specified multiple times"),
         )
         .arg(
             Arg::new("auto-create-version")
                 .long("auto-create-version")
+                .action(ArgAction::SetTrue)
                 .help("Automatically create the pacticipant version if it does not exist"),
         )
         .arg(
             Arg::new("tag-with-git-branch")
                 .short('g')
                 .long("tag-with-git-branch")
+                .action(ArgAction::SetTrue)
                 .help("Tag pacticipant version with the name of the current git branch"),
         )
+        .args(add_broker_auth_arguments())
+        .args(add_verbose_arguments())
 }
 fn add_describe_version_subcommand() -> Command {
     Command::new("describe-version")
     .about("Describes a pacticipant version. If no version or tag is specified, the latest version is described.")
+    .args(add_broker_auth_arguments())
     .arg(Arg::new("pacticipant")
         .short('a')
         .long("pacticipant")
@@ -761,17 +961,22 @@ fn add_describe_version_subcommand() -> Command {
         .short('e')
         .long("version")
         .value_name("VERSION")
+        .conflicts_with("latest")
         .help("The pacticipant version number"))
     .arg(Arg::new("latest")
         .short('l')
         .long("latest")
         .value_name("TAG")
+        .num_args(0..=1)
+        .default_missing_value("")
         .help("Describe the latest pacticipant version. Optionally specify a TAG to describe the latest version with the specified tag"))
-        .args(add_output_arguments())
+        .args(add_table_output_arguments())
+        .args(add_verbose_arguments())
 }
 fn add_create_or_update_version_subcommand() -> Command {
     Command::new("create-or-update-version")
         .about("Create or update pacticipant version by version number")
+        .args(add_broker_auth_arguments())
         .arg(
             Arg::new("pacticipant")
                 .short('a')
@@ -799,10 +1004,47 @@ fn add_create_or_update_version_subcommand() -> Command {
                 .short('t')
                 .long("tag")
                 .value_name("TAG")
-                .num_args(0..=1)
+                .action(ArgAction::Append)
                 .help("Tag name for pacticipant version. Can be specified multiple times"),
         )
-        .args(add_output_arguments())
+        .args(add_table_output_arguments())
+        .args(add_verbose_arguments())
+}
+fn add_navigate_subcommand() -> Command {
+    Command::new("navigate")
+    .visible_alias("get")
+    .about("Follow a chain of HAL relations from the index resource of the Pact Broker, and display the resource at the end")
+    .arg(Arg::new("RELATION")
+        .num_args(0..)
+        .help("Relations to follow in order, like pb:latest-provider-pacts. Select one of several links for a relation by name with RELATION[NAME]. The index resource is displayed if no relations are given"))
+    .arg(Arg::new("param")
+        .long("param")
+        .value_name("NAME=VALUE")
+        .action(ArgAction::Append)
+        .help("Value for the templated links, like provider=Foo. Can be specified multiple times, and a name given more than once is expanded as a list"))
+    .arg(Arg::new("links")
+        .long("links")
+        .action(ArgAction::SetTrue)
+        .help("Only display the links of the resource"))
+    .arg(Arg::new("output")
+        .short('o')
+        .long("output")
+        .value_name("OUTPUT")
+        .value_parser(clap::builder::PossibleValuesParser::new(["json", "yaml", "tree"]))
+        .default_value("json")
+        .help("json, yaml or tree"))
+    .args(add_broker_auth_arguments())
+    .args(add_verbose_arguments())
+}
+fn add_browse_subcommand() -> Command {
+    Command::new("browse")
+    .about("Browse the pacticipants, pacts, versions, environments and matrix of the Pact Broker in an interactive terminal UI")
+    .arg(Arg::new("pacticipant")
+        .long("pacticipant")
+        .value_name("PACTICIPANT")
+        .help("Start with the versions, branches, environments, pacts and matrix of the pacticipant"))
+    .args(add_broker_auth_arguments())
+    .args(add_verbose_arguments())
 }
 fn add_generate_uuid_subcommand() -> Command {
     Command::new("generate-uuid")
@@ -821,6 +1063,7 @@ fn add_publish_provider_contract_subcommand() -> Command {
     .arg(Arg::new("provider")
         .long("provider")
         .value_name("PROVIDER")
+        .required(true)
         .help("The provider name"))
     .arg(Arg::new("provider-app-version")
         .short('a')
@@ -837,7 +1080,7 @@ fn add_publish_provider_contract_subcommand() -> Command {
         .short('t')
         .long("tag")
         .value_name("TAG")
-        .num_args(0..=1)
+        .action(ArgAction::Append)
         .help("Tag name for provider version. Can be specified multiple times."))
     .arg(Arg::new("specification")
         .long("specification")
@@ -847,13 +1090,20 @@ fn add_publish_provider_contract_subcommand() -> Command {
     .arg(Arg::new("content-type")
         .long("content-type")
         .value_name("CONTENT_TYPE")
-        .help("The content type. eg. application/yml"))
+        .help("The content type. eg. application/yml. Detected from the file extension if not specified"))
     .arg(Arg::new("verification-success")
         .long("verification-success")
+        .action(ArgAction::SetTrue)
         .help("Whether or not the self verification passed successfully."))
+    .arg(Arg::new("no-verification-success")
+        .long("no-verification-success")
+        .action(ArgAction::SetTrue)
+        .conflicts_with("verification-success")
+        .help("The self verification did not pass successfully."))
     .arg(Arg::new("verification-exit-code")
         .long("verification-exit-code")
         .value_name("N")
+        .value_parser(clap::value_parser!(i32))
         .help("The exit code of the verification process. Can be used instead of --verification-success|--no-verification-success for a simpler build script."))
     .arg(Arg::new("verification-results")
         .long("verification-results")
@@ -879,6 +1129,16 @@ fn add_publish_provider_contract_subcommand() -> Command {
         .long("build-url")
         .value_name("BUILD_URL")
         .help("The build URL that created the provider contract"))
-.args(add_output_arguments())
+.args(add_table_output_arguments())
 .args(add_verbose_arguments())
-}
\ No newline at end of file
+}
+
+/// Parses a command line (without the program name) and returns the arguments of the nested
+/// subcommand, e.g. `pact-broker can-i-deploy ...`
+#[cfg(test)]
+pub(crate) fn subcommand_args(command_line: &[&str]) -> clap::ArgMatches {
+    let matches = build_cli().get_matches_from(["pact_cli"].iter().chain(command_line));
+    let (_, parent) = matches.subcommand().unwrap();
+    let (_, args) = parent.subcommand().unwrap();
+    args.clone()
+}
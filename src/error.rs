@@ -0,0 +1,190 @@
+//! Errors returned by the CLI commands, and the exit codes they map to (listed in the README)
+
+use ansi_term::Colour::*;
+use serde_json::{json, Value};
+use thiserror::Error;
+
+use crate::pact_broker::PactBrokerError;
+
+/// Exit code when the command ran, but the result was a failure
+pub const EXIT_FAILURE: i32 = 1;
+/// Exit code for invalid arguments, configuration or input files
+pub const EXIT_INVALID_ARGUMENTS: i32 = 2;
+/// Exit code when the resource was not found in the Pact Broker
+pub const EXIT_NOT_FOUND: i32 = 3;
+/// Exit code when the Pact Broker rejected the request due to validation errors
+pub const EXIT_VALIDATION_ERROR: i32 = 4;
+/// Exit code when the Pact Broker could not be reached, or the request to it failed
+pub const EXIT_BROKER_UNAVAILABLE: i32 = 5;
+/// Exit code when the Pact Broker returned a response that could not be used
+pub const EXIT_INVALID_RESPONSE: i32 = 6;
+
+/// Error returned by a CLI command
+#[derive(Debug, Clone, Error)]
+pub enum CliError {
+  /// The command arguments, configuration or input files were invalid
+  #[error("{0}")]
+  InvalidArguments(String),
+  /// The request to the Pact Broker failed
+  #[error("{}", broker_message(.context, .error))]
+  Broker {
+    /// Description of what the command was trying to do
+    context: Option<String>,
+    /// Error from the Pact Broker client
+    error: PactBrokerError
+  },
+  /// The command ran, but the result was a failure
+  #[error("{0}")]
+  Failed(String),
+  /// The pacticipant versions are not safe to deploy or merge. The matrix has already been
+  /// displayed, so there is nothing further to report.
+  #[error("The pacticipant versions are not safe to deploy")]
+  NotDeployable
+}
+
+fn broker_message(context: &Option<String>, error: &PactBrokerError) -> String {
+  match context {
+    Some(context) => format!("{} - {}", context, error),
+    None => error.to_string()
+  }
+}
+
+impl CliError {
+  /// Wraps an error from the Pact Broker client with a description of what the command was doing
+  pub fn broker(context: impl Into<String>, error: PactBrokerError) -> CliError {
+    CliError::Broker { context: Some(context.into()), error }
+  }
+
+  /// Exit code for the error
+  pub fn exit_code(&self) -> i32 {
+    match self {
+      CliError::InvalidArguments(_) => EXIT_INVALID_ARGUMENTS,
+      CliError::Broker { error, .. } => match error {
+        PactBrokerError::NotFound(_) => EXIT_NOT_FOUND,
+        PactBrokerError::ValidationError(_) => EXIT_VALIDATION_ERROR,
+        PactBrokerError::IoError(_) => EXIT_BROKER_UNAVAILABLE,
+        PactBrokerError::LinkError(_) | PactBrokerError::ContentError(_) | PactBrokerError::UrlError(_) => EXIT_INVALID_RESPONSE
+      },
+      CliError::Failed(_) | CliError::NotDeployable => EXIT_FAILURE
+    }
+  }
+
+  /// Short identifier for the type of error, used in the JSON output
+  pub fn error_type(&self) -> &'static str {
+    match self {
+      CliError::InvalidArguments(_) => "invalid_arguments",
+      CliError::Broker { error, .. } => match error {
+        PactBrokerError::NotFound(_) => "not_found",
+        PactBrokerError::ValidationError(_) => "validation_error",
+        PactBrokerError::IoError(_) => "broker_unavailable",
+        PactBrokerError::LinkError(_) => "link_error",
+        PactBrokerError::ContentError(_) => "content_error",
+        PactBrokerError::UrlError(_) => "url_error"
+      },
+      CliError::Failed(_) => "failed",
+      CliError::NotDeployable => "not_deployable"
+    }
+  }
+
+  /// Returns the error as JSON, for when the output format is JSON
+  pub fn to_json(&self) -> Value {
+    let mut error = json!({
+      "type": self.error_type(),
+      "message": self.to_string(),
+      "exitCode": self.exit_code()
+    });
+    if let CliError::Broker { context, error: PactBrokerError::ValidationError(errors) } = self {
+      error["message"] = json!(validation_message(context));
+      error["errors"] = json!(errors);
+    }
+    json!({ "error": error })
+  }
+
+  /// Reports the error, returning the exit code. With JSON output, the error is printed to standard
+  /// output as a JSON document, otherwise a message is printed to standard error.
+  pub fn report(&self, json_output: bool) -> i32 {
+    if json_output {
+      // The matrix has already been output with the result
+      if !matches!(self, CliError::NotDeployable) {
+        println!("{}", self.to_json());
+      }
+    } else {
+      match self {
+        CliError::Broker { context, error: PactBrokerError::ValidationError(errors) } => {
+          eprintln!("{}", Red.paint(validation_message(context)));
+          for error in errors {
+            eprintln!("  {}", Red.paint(error));
+          }
+        }
+        CliError::NotDeployable => {}
+        _ => eprintln!("{}", Red.paint(self.to_string()))
+      }
+    }
+    self.exit_code()
+  }
+}
+
+fn validation_message(context: &Option<String>) -> String {
+  match context {
+    Some(context) => format!("{} due to the following errors:", context),
+    None => "The Pact Broker rejected the request due to the following errors:".to_string()
+  }
+}
+
+impl From<PactBrokerError> for CliError {
+  fn from(error: PactBrokerError) -> Self {
+    CliError::Broker { context: None, error }
+  }
+}
+
+impl From<anyhow::Error> for CliError {
+  fn from(error: anyhow::Error) -> Self {
+    match error.downcast::<PactBrokerError>() {
+      Ok(error) => error.into(),
+      Err(error) => CliError::InvalidArguments(error.to_string())
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use super::*;
+
+  #[test]
+  fn exit_code_depends_on_the_type_of_error() {
+    expect!(CliError::InvalidArguments("bad".to_string()).exit_code()).to(be_equal_to(2));
+    expect!(CliError::from(PactBrokerError::NotFound("x".to_string())).exit_code()).to(be_equal_to(3));
+    expect!(CliError::from(PactBrokerError::ValidationError(vec![])).exit_code()).to(be_equal_to(4));
+    expect!(CliError::from(PactBrokerError::IoError("x".to_string())).exit_code()).to(be_equal_to(5));
+    expect!(CliError::from(PactBrokerError::LinkError("x".to_string())).exit_code()).to(be_equal_to(6));
+    expect!(CliError::Failed("x".to_string()).exit_code()).to(be_equal_to(1));
+    expect!(CliError::NotDeployable.exit_code()).to(be_equal_to(1));
+    expect!(CliError::from(anyhow::anyhow!(PactBrokerError::NotFound("x".to_string()))).exit_code()).to(be_equal_to(3));
+    expect!(CliError::from(anyhow::anyhow!("bad")).exit_code()).to(be_equal_to(2));
+  }
+
+  #[test]
+  fn to_json_includes_the_validation_errors() {
+    let error = CliError::broker("Failed to publish pacts for consumer 'Foo'",
+      PactBrokerError::ValidationError(vec!["branch: can't be blank".to_string()]));
+    expect!(error.to_json()).to(be_equal_to(json!({
+      "error": {
+        "type": "validation_error",
+        "message": "Failed to publish pacts for consumer 'Foo' due to the following errors:",
+        "errors": ["branch: can't be blank"],
+        "exitCode": 4
+      }
+    })));
+    expect!(CliError::from(PactBrokerError::NotFound("Pacticipant 'Foo' does not exist in the Pact Broker".to_string())).to_json())
+      .to(be_equal_to(json!({
+        "error": {
+          "type": "not_found",
+          "message": "Link/Resource was not found - Pacticipant 'Foo' does not exist in the Pact Broker",
+          "exitCode": 3
+        }
+      })));
+  }
+}
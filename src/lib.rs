@@ -5,7 +5,9 @@ use log::{LevelFilter, SetLoggerError};
 use simplelog::{ColorChoice, Config, TerminalMode, TermLogger};
 
 pub mod verification;
-mod cli;
+pub mod cli;
+pub mod error;
+pub mod pact_broker;
 pub fn setup_loggers(level: &str) -> Result<(), SetLoggerError> {
   let log_level = match level {
     "none" => LevelFilter::Off,
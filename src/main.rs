@@ -1,342 +1,111 @@
 // use std::collections::HashMap;
-mod cli;
+use clap::ArgMatches;
 use clap_complete::{generate_to, Shell};
-use pact_broker::{HALClient, Link, PactBrokerError};
-use serde_json::Value;
+use pact_cli::cli;
+use pact_cli::error::CliError;
+use pact_cli::pact_broker::utils::is_json_output;
+use pact_cli::pact_broker::{branches, browse, deployments, environments, matrix, navigate, pacticipants, pacts, provider_contracts, publish_pacts, releases, verification_results, versions, webhooks};
 use std::str::FromStr;
-mod pact_broker;
-use maplit::hashmap;
-use pact_models::http_utils::HttpAuth;
-use tabled::{builder::Builder, settings::Style};
 
-fn get_broker_url(args: &clap::ArgMatches) -> String {
-    args.get_one::<String>("broker-base-url")
-        .expect("url is required")
-        .to_string()
-}
-// setup client with broker url and credentials
-fn get_auth(args: &clap::ArgMatches) -> HttpAuth {
-    let token = args.try_get_one::<String>("broker-token");
-    let username = args.try_get_one::<String>("broker-username");
-    let password = args.try_get_one::<String>("broker-password");
-    let auth;
-
-    match token {
-        Ok(Some(token)) => {
-            auth = HttpAuth::Token(token.to_string());
-        }
-        Ok(None) => match username {
-            Ok(Some(username)) => match password {
-                Ok(Some(password)) => {
-                    auth = HttpAuth::User(username.to_string(), Some(password.to_string()));
-                }
-                Ok(None) => {
-                    auth = HttpAuth::User(username.to_string(), None);
-                }
-                Err(_) => todo!(),
-            },
-            Ok(None) => {
-                auth = HttpAuth::None;
-            }
-            Err(_) => todo!(),
-        },
-        Err(_) => todo!(),
-    }
-
-    auth
-}
-
-async fn get_broker_relation(
-    hal_client: HALClient,
-    relation: String,
-    broker_url: String,
-) -> String {
-    let index_res: Result<Value, PactBrokerError> = hal_client.clone().fetch("/").await;
-    let index_res_clone = index_res.clone().unwrap();
-    index_res_clone
-        .get("_links")
-        .unwrap()
-        .get(relation)
-        .unwrap()
-        .get("href")
-        .unwrap()
-        .to_string()
-        .split(&broker_url)
-        .collect::<Vec<&str>>()[1]
-        .to_string()
-        .replace("\"", "")
-        .to_string()
-}
-
-async fn follow_broker_relation(
-    hal_client: HALClient,
-    relation: String,
-    relation_href: String,
-) -> Result<Value, PactBrokerError> {
-    let link = Link {
-        name: relation,
-        href: Some(relation_href),
-        templated: false,
-        title: None,
-    };
-    let template_values = hashmap! {};
-    hal_client.fetch_url(&link, &template_values).await
-}
-
-fn generate_table(res: &Value, columns: Vec<&str>, names: Vec<Vec<&str>>) {
-    let mut builder = Builder::default();
-    builder.push_record(columns);
-
-    if let Some(items) = res.get("pacts").unwrap().as_array() {
-        for item in items {
-            let mut values = vec![item; names.len()];
-
-            for (i, name) in names.iter().enumerate() {
-                for n in name.clone() {
-                    values[i] = values[i].get(n).unwrap();
-                }
-            }
-
-            let records: Vec<String> = values.iter().map(|v| v.to_string()).collect();
-            builder.push_record(records.as_slice());
-        }
+/// Reports the error from the command and exits with its exit code. The error is output as JSON
+/// if the command output format is JSON.
+fn exit_on_error(result: Result<(), CliError>, args: &ArgMatches) {
+    if let Err(err) = result {
+        std::process::exit(err.report(is_json_output(args)));
     }
-    let mut table = builder.build();
-    table.with(Style::rounded());
-
-    println!("{:#}", table);
 }
 
 #[tokio::main]
 pub async fn main() {
     let _m = cli::build_cli().get_matches();
+    // Warnings (like the retries of failed requests to the Pact Broker) are logged to standard error
+    let _ = pact_cli::setup_loggers("warn");
 
     match _m.subcommand() {
         Some(("pact-broker", args)) => {
             match args.subcommand() {
                 Some(("publish", args)) => {
-                    print!("{:?}", args);
-                    // // Ok(());
+                    exit_on_error(publish_pacts::publish(args).await, args);
+                }
+                Some(("publish-verification-results", args)) => {
+                    exit_on_error(verification_results::publish_verification_results(args).await, args);
                 }
                 Some(("list-latest-pact-versions", args)) => {
-                    // Handle list-latest-pact-versions command
-
-                    // setup client with broker url and credentials
-                    let broker_url = get_broker_url(args);
-                    let auth = get_auth(args);
-                    // query pact broker index and get hal relation link
-                    let hal_client: HALClient =
-                        HALClient::with_url(&broker_url, Some(auth.clone()));
-                    let pb_latest_pact_versions_href_path = get_broker_relation(
-                        hal_client.clone(),
-                        "pb:latest-pact-versions".to_string(),
-                        broker_url,
-                    )
-                    .await;
-                    // query the hal relation link to get the latest pact versions
-                    let res = follow_broker_relation(
-                        hal_client.clone(),
-                        "pb:latest-pact-versions".to_string(),
-                        pb_latest_pact_versions_href_path,
-                    )
-                    .await;
-
-                    // handle user args for additional processing
-                    let output: Result<Option<&String>, clap::parser::MatchesError> =
-                        args.try_get_one::<String>("output");
-
-                    // render result
-                    match output {
-                        Ok(Some(output)) => {
-                            if output == "json" {
-                                let json: String = serde_json::to_string(&res.unwrap()).unwrap();
-                                println!("{}", json);
-                            } else if output == "table" {
-                                if let Ok(res) = res {
-                                    generate_table(
-                                        &res,
-                                        vec![
-                                            "CONSUMER",
-                                            "CONSUMER_VERSION",
-                                            "PROVIDER",
-                                            "CREATED_AT",
-                                        ],
-                                        vec![
-                                            vec!["_embedded", "consumer", "name"],
-                                            vec![
-                                                "_embedded",
-                                                "consumer",
-                                                "_embedded",
-                                                "version",
-                                                "number",
-                                            ],
-                                            vec!["_embedded", "provider", "name"],
-                                            vec!["createdAt"],
-                                        ],
-                                    );
-                                }
-                            }
-                        }
-                        Ok(None) => {
-                            println!("{:?}", res.clone());
-                        }
-                        Err(_) => todo!(),
-                    }
+                    exit_on_error(pacts::list_latest_pact_versions(args).await, args);
                 }
                 Some(("create-environment", args)) => {
-                    // Handle create-environment command
-                    // Ok(());
+                    exit_on_error(environments::create_environment(args).await, args);
                 }
                 Some(("update-environment", args)) => {
-                    // Handle update-environment command
-                    // Ok(());
+                    exit_on_error(environments::update_environment(args).await, args);
                 }
                 Some(("describe-environment", args)) => {
-                    // Handle describe-environment command
-                    // Ok(());
+                    exit_on_error(environments::describe_environment(args).await, args);
                 }
                 Some(("delete-environment", args)) => {
-                    // Handle delete-environment command
-                    // Ok(());
+                    exit_on_error(environments::delete_environment(args).await, args);
                 }
                 Some(("list-environments", args)) => {
-                    // Handle list-environments command
-                    // Ok(());
+                    exit_on_error(environments::list_environments(args).await, args);
                 }
                 Some(("record-deployment", args)) => {
-                    // Handle record-deployment command
-                    // Ok(());
+                    exit_on_error(deployments::record_deployment(args).await, args);
                 }
                 Some(("record-undeployment", args)) => {
-                    // Handle record-undeployment command
-                    // Ok(());
+                    exit_on_error(deployments::record_undeployment(args).await, args);
                 }
                 Some(("record-release", args)) => {
-                    // Handle record-release command
-                    // Ok(());
+                    exit_on_error(releases::record_release(args).await, args);
                 }
                 Some(("record-support-ended", args)) => {
-                    // Handle record-support-ended command
-                    // Ok(());
+                    exit_on_error(releases::record_support_ended(args).await, args);
                 }
                 Some(("can-i-deploy", args)) => {
-                    // Handle can-i-deploy command
-                    // setup client with broker url and credentials
-                    let broker_url = get_broker_url(args);
-                    let auth = get_auth(args);
-                    // query pact broker index and get hal relation link
-                    let hal_client: HALClient =
-                        HALClient::with_url(&broker_url, Some(auth.clone()));
-                    let matrix_href_path = "/matrix?pacticipant=Example+App&latest=true&latestby=cvp&latest=true".to_string();
-                    // let matrix_href_path = "/matrix?q[][pacticipant]=Example+App&q[][latest]=true&latestby=cvp&latest=true".to_string();
-                    // query the hal relation link to get the latest pact versions
-                    let res = follow_broker_relation(
-                        hal_client.clone(),
-                        "pb:latest-pact-versions".to_string(),
-                        matrix_href_path,
-                    )
-                    .await;
-                    match res {
-                        Ok(res) => {
-                            // handle user args for additional processing
-                            let output: Result<Option<&String>, clap::parser::MatchesError> =
-                                args.try_get_one::<String>("output");
-
-                            // render result
-                            match output {
-                                Ok(Some(output)) => {
-                                    if output == "json" {
-                                        let json: String =
-                                            serde_json::to_string(&res.clone()).unwrap();
-                                        println!("{}", json);
-                                    } else if output == "table" {
-                                        generate_table(
-                                            &res,
-                                            vec![
-                                                "CONSUMER",
-                                                "CONSUMER_VERSION",
-                                                "PROVIDER",
-                                                "CREATED_AT",
-                                            ],
-                                            vec![
-                                                vec!["_embedded", "consumer", "name"],
-                                                vec![
-                                                    "_embedded",
-                                                    "consumer",
-                                                    "_embedded",
-                                                    "version",
-                                                    "number",
-                                                ],
-                                                vec!["_embedded", "provider", "name"],
-                                                vec!["createdAt"],
-                                            ],
-                                        );
-                                    }
-                                }
-                                Ok(None) => {
-                                    println!("{:?}", res.clone());
-                                }
-                                Err(res) => {
-                                    println!("{:?}", res);
-                                    // os.exit(1)
-                                }
-                            }
-                        }
-                        Err(res) => {
-                            println!("{:?}", res);
-                            // os.exit(1)
-                        }
-                    }
+                    exit_on_error(matrix::can_i_deploy(args).await, args);
                 }
                 Some(("can-i-merge", args)) => {
-                    // Handle can-i-merge command
-                    // Ok(());
+                    exit_on_error(matrix::can_i_merge(args).await, args);
                 }
                 Some(("create-or-update-pacticipant", args)) => {
-                    // Handle create-or-update-pacticipant command
-                    // Ok(());
+                    exit_on_error(pacticipants::create_or_update_pacticipant(args).await, args);
                 }
 
                 Some(("describe-pacticipant", args)) => {
-                    // Handle describe-pacticipants command
-                    // Ok(());
+                    exit_on_error(pacticipants::describe_pacticipant(args).await, args);
                 }
                 Some(("list-pacticipants", args)) => {
-                    // Handle list-pacticipants command
-                    // Ok(());
+                    exit_on_error(pacticipants::list_pacticipants(args).await, args);
                 }
 
                 Some(("create-webhook", args)) => {
-                    // Handle create-webhook command
-                    // Ok(());
+                    exit_on_error(webhooks::create_webhook(args).await, args);
                 }
                 Some(("create-or-update-webhook", args)) => {
-                    // Handle create-or-update-webhook command
-                    // Ok(());
+                    exit_on_error(webhooks::create_or_update_webhook(args).await, args);
                 }
                 Some(("test-webhook", args)) => {
-                    // Handle test-webhook command
-
-                    // Ok(());
+                    exit_on_error(webhooks::test_webhook(args).await, args);
                 }
 
                 Some(("delete-branch", args)) => {
-                    // Handle delete-branch command
-                    // Ok(());
+                    exit_on_error(branches::delete_branch(args).await, args);
                 }
                 Some(("create-version-tag", args)) => {
-                    // Handle create-version-tag command
-                    // Ok(());
+                    exit_on_error(versions::create_version_tag(args).await, args);
                 }
                 Some(("describe-version", args)) => {
-                    // Handle describe-version command
-                    // Ok(());
+                    exit_on_error(versions::describe_version(args).await, args);
                 }
                 Some(("create-or-update-version", args)) => {
-                    // Handle create-or-update-version command
-                    // Ok(());
+                    exit_on_error(versions::create_or_update_version(args).await, args);
+                }
+                Some(("navigate", args)) => {
+                    exit_on_error(navigate::navigate(args).await, args);
+                }
+                Some(("browse", args)) => {
+                    exit_on_error(browse::browse(args).await, args);
                 }
-                Some(("generate-uuid", args)) => {
+                Some(("generate-uuid", _args)) => {
                     // Handle generate-uuid command
                     // Ok(());
                 }
@@ -351,9 +120,7 @@ pub async fn main() {
         Some(("pactflow", args)) => {
             match args.subcommand() {
                 Some(("publish-provider-contract", args)) => {
-                    print!("{:?}", args);
-
-                    // Ok(());
+                    exit_on_error(provider_contracts::publish_provider_contract(args).await, args);
                 }
                 _ => {
                     println!("⚠️  No option provided, try running pactflow --help");
@@ -372,8 +139,13 @@ pub async fn main() {
                 .get_one::<String>("dir")
                 .expect("a directory is expected")
                 .to_string();
-            let shell_enum = Shell::from_str(&shell).unwrap();
-            let _ = generate_to(shell_enum, &mut cmd, "pact_cli".to_string(), &out_dir);
+            let shell_enum = match Shell::from_str(&shell) {
+                Ok(shell) => shell,
+                Err(err) => std::process::exit(CliError::InvalidArguments(err).report(false))
+            };
+            if let Err(err) = generate_to(shell_enum, &mut cmd, "pact_cli".to_string(), &out_dir) {
+                std::process::exit(CliError::Failed(format!("Failed to write the shell completions to {} - {}", out_dir, err)).report(false));
+            }
             print!(
                 "ℹ️  {} shell completions for pact_cli written to {}",
                 &shell_enum, &out_dir
@@ -382,7 +154,7 @@ pub async fn main() {
             // Ok(());
         }
         _ => {
-            cli::build_cli().print_help().unwrap();
+            let _ = cli::build_cli().print_help();
 
             // Ok(());
         }
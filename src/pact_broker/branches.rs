@@ -0,0 +1,276 @@
+//! Functions for managing pacticipant branches using the `pb:pacticipant-branch` relation
+
+use std::collections::HashMap;
+
+use ansi_term::Colour::*;
+use clap::ArgMatches;
+use futures::{future, stream, StreamExt, TryStreamExt};
+use maplit::hashmap;
+use serde_json::{json, Value};
+
+use crate::error::CliError;
+
+use super::{HALClient, PactBrokerError};
+use super::environments::{currently_deployed_versions, fetch_environments};
+use super::output::{print_output, OutputFormat, OutputOptions, ResourceTable, TableRow};
+use super::resources::{from_json, Version};
+use super::utils::{broker_client, json_str};
+
+/// Fetches the branch of the pacticipant
+async fn fetch_branch(hal_client: &HALClient, pacticipant: &str, branch: &str) -> Result<HALClient, PactBrokerError> {
+  hal_client.clone()
+    .navigate("pb:pacticipant-branch", &hashmap!{
+      "pacticipant".to_string() => pacticipant.to_string(),
+      "branch".to_string() => branch.to_string()
+    })
+    .await
+    .map_err(|err| match err {
+      PactBrokerError::NotFound(_) => PactBrokerError::NotFound(
+        format!("Branch '{}' of pacticipant '{}' does not exist in the Pact Broker", branch, pacticipant)),
+      _ => err
+    })
+}
+
+/// Returns the pacts published by the version, from the `pb:pact-versions` links
+pub fn version_pacts(version: &Value) -> Vec<Value> {
+  match version.pointer("/_links/pb:pact-versions") {
+    Some(Value::Array(links)) => links.iter()
+      .map(|link| json!({ "name": json_str(link, "/name"), "href": json_str(link, "/href") }))
+      .collect(),
+    Some(link @ Value::Object(_)) => vec![json!({ "name": json_str(link, "/name"), "href": json_str(link, "/href") })],
+    _ => vec![]
+  }
+}
+
+/// Number of version resources fetched at once
+const CONCURRENT_FETCHES: usize = 8;
+
+/// Fetches the versions on the branch. If the version summaries in the branch versions collection
+/// do not include the pact links, the full version resources are fetched, several at a time.
+async fn branch_versions(hal_client: &HALClient, branch: &Value) -> Result<Vec<Value>, PactBrokerError> {
+  let versions_url = json_str(branch, "/_links/pb:versions/href");
+  if versions_url.is_empty() {
+    return Ok(vec![]);
+  }
+
+  stream::iter(hal_client.fetch_all_pages(versions_url, "versions").await?)
+    .map(|version| async move {
+      let self_url = json_str(&version, "/_links/self/href");
+      if version.pointer("/_links/pb:pact-versions").is_none() && !self_url.is_empty() {
+        hal_client.clone().fetch(self_url).await
+      } else {
+        Ok(version)
+      }
+    })
+    .buffered(CONCURRENT_FETCHES)
+    .try_collect()
+    .await
+}
+
+/// Collects the environments each version of the pacticipant is currently deployed or released to,
+/// keyed by version number
+async fn deployed_and_released_versions(hal_client: &HALClient, pacticipant: &str) -> Result<HashMap<String, Vec<String>>, PactBrokerError> {
+  let environments = match fetch_environments(hal_client).await {
+    Ok(environments) => environments,
+    Err(PactBrokerError::LinkError(_)) => return Ok(HashMap::new()),
+    Err(err) => return Err(err)
+  };
+  let currently_deployed = future::try_join_all(environments.iter()
+    .map(|environment| currently_deployed_versions(hal_client, environment, pacticipant))).await?;
+
+  let mut reasons: HashMap<String, Vec<String>> = HashMap::new();
+  for (environment, (deployed, released)) in environments.iter().zip(currently_deployed) {
+    let deployed = deployed.into_iter().filter_map(|deployed| deployed.embedded.version)
+      .map(|version| (version.number, format!("deployed to {}", environment.name)));
+    let released = released.into_iter().filter_map(|released| released.embedded.version)
+      .map(|version| (version.number, format!("released to {}", environment.name)));
+    for (number, reason) in deployed.chain(released) {
+      let version_reasons = reasons.entry(number).or_default();
+      if !version_reasons.contains(&reason) {
+        version_reasons.push(reason);
+      }
+    }
+  }
+  Ok(reasons)
+}
+
+/// Returns the reasons the version remains selectable once it is removed from the branch: any other
+/// branches it is on, its tags and the environments it is deployed or released to
+pub fn retained_by(version: &Value, branch: &str, deployments: &HashMap<String, Vec<String>>) -> Vec<String> {
+  let resource = from_json::<Version>(version).unwrap_or_default();
+  resource.branch_names().iter()
+    .filter(|name| name.as_str() != branch)
+    .map(|name| format!("branch {}", name))
+    .chain(resource.tag_names().iter().map(|name| format!("tag {}", name)))
+    .chain(deployments.get(json_str(version, "/number")).cloned().unwrap_or_default())
+    .collect()
+}
+
+/// Version on the branch with the pacts it published and the reasons it remains selectable,
+/// displayed for a dry run
+struct BranchVersion(Value);
+
+impl TableRow for BranchVersion {
+  fn headers() -> Vec<&'static str> {
+    vec!["VERSION", "PACTS", "RETAINED BY"]
+  }
+
+  fn row(&self) -> Vec<String> {
+    let values = |key: &str, pointer: &str| self.0.get(key).and_then(|values| values.as_array()).cloned()
+      .unwrap_or_default()
+      .iter()
+      .map(|value| json_str(value, pointer).to_string())
+      .collect::<Vec<_>>()
+      .join(", ");
+    vec![json_str(&self.0, "/number").to_string(), values("pacts", "/name"), values("retainedBy", "")]
+  }
+}
+
+/// Branch that was deleted
+struct DeletedBranch {
+  pacticipant: String,
+  branch: String
+}
+
+impl TableRow for DeletedBranch {
+  fn headers() -> Vec<&'static str> {
+    vec!["PACTICIPANT", "BRANCH", "DELETED"]
+  }
+
+  fn row(&self) -> Vec<String> {
+    vec![self.pacticipant.clone(), self.branch.clone(), true.to_string()]
+  }
+}
+
+fn display_branch_impact(pacticipant: &str, branch: &str, versions: &[Value]) {
+  if versions.is_empty() {
+    println!("Branch '{}' of pacticipant '{}' has no versions", branch, pacticipant);
+    return;
+  }
+  let retained_by = |version: &Value| version.get("retainedBy").and_then(|reasons| reasons.as_array()).cloned().unwrap_or_default();
+  let (retained, removed): (Vec<&Value>, Vec<&Value>) = versions.iter()
+    .partition(|version| !retained_by(version).is_empty());
+
+  if removed.is_empty() {
+    println!("Deleting branch '{}' of pacticipant '{}' will not make any versions or pacts unselectable", branch, pacticipant);
+  } else {
+    println!("Deleting branch '{}' of pacticipant '{}' will make the following versions and pacts no longer selectable through consumer version selectors or WIP pacts:",
+      branch, pacticipant);
+    for version in removed {
+      println!("  Version {}", json_str(version, "/number"));
+      for pact in version.get("pacts").and_then(|pacts| pacts.as_array()).cloned().unwrap_or_default() {
+        println!("    {}", json_str(&pact, "/name"));
+      }
+    }
+  }
+  if !retained.is_empty() {
+    println!("The following versions will be removed from the branch but remain selectable:");
+    for version in retained {
+      let reasons = retained_by(version).iter().filter_map(|reason| reason.as_str().map(|reason| reason.to_string())).collect::<Vec<_>>();
+      println!("  Version {} ({})", json_str(version, "/number"), reasons.join(", "));
+    }
+  }
+}
+
+/// Handles the `pact-broker delete-branch` command. With `--dry-run`, the versions and pacts on the
+/// branch are listed and the branch is not deleted.
+pub async fn delete_branch(args: &ArgMatches) -> Result<(), CliError> {
+  let pacticipant = args.get_one::<String>("pacticipant").cloned().unwrap_or_default();
+  let branch = args.get_one::<String>("branch").cloned().unwrap_or_default();
+  let dry_run = args.get_flag("dry-run");
+  let options = OutputOptions::from_args(args);
+  let hal_client = broker_client(args)?;
+
+  let result = async {
+    let hal_client = hal_client.with_index().await?;
+    let branch_client = fetch_branch(&hal_client, &pacticipant, &branch).await?;
+    let branch_resource = branch_client.path_info.clone().unwrap_or_default();
+    if dry_run {
+      let deployments = deployed_and_released_versions(&hal_client, &pacticipant).await?;
+      let versions = branch_versions(&hal_client, &branch_resource).await?.iter()
+        .map(|version| json!({
+          "number": json_str(version, "/number"),
+          "pacts": version_pacts(version),
+          "retainedBy": retained_by(version, &branch, &deployments)
+        }))
+        .collect::<Vec<_>>();
+      Ok(Some(versions))
+    } else {
+      let url = branch_client.link_url("self", &hashmap!{})?;
+      branch_client.delete(url.as_str()).await?;
+      Ok::<_, PactBrokerError>(None)
+    }
+  }.await;
+
+  match result {
+    Ok(Some(versions)) => {
+      if options.format == OutputFormat::Table {
+        display_branch_impact(&pacticipant, &branch, &versions);
+        println!("{}", Yellow.paint("Dry run enabled - the branch was not deleted"));
+        Ok(())
+      } else {
+        let document = json!({
+          "pacticipant": pacticipant,
+          "branch": branch,
+          "versions": versions,
+          "deleted": false
+        });
+        print_output(&ResourceTable::new(&document, versions.into_iter().map(BranchVersion).collect()), &options)
+      }
+    }
+    Ok(None) => {
+      if options.format == OutputFormat::Table {
+        println!("Deleted branch '{}' of pacticipant '{}'", branch, pacticipant);
+        Ok(())
+      } else {
+        let document = json!({
+          "pacticipant": pacticipant,
+          "branch": branch,
+          "deleted": true
+        });
+        print_output(&ResourceTable::single(&document, DeletedBranch { pacticipant, branch }), &options)
+      }
+    }
+    Err(err) => Err(err.into())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use super::*;
+
+  #[test]
+  fn version_pacts_returns_the_pact_version_links() {
+    let version = json!({
+      "number": "1",
+      "_links": {
+        "pb:pact-versions": [
+          { "name": "Pact between Foo (1) and Bar", "href": "http://localhost/pacts/1" }
+        ]
+      }
+    });
+    expect!(version_pacts(&version)).to(be_equal_to(vec![
+      json!({ "name": "Pact between Foo (1) and Bar", "href": "http://localhost/pacts/1" })
+    ]));
+    expect!(version_pacts(&json!({ "_links": {} }))).to(be_equal_to(Vec::<Value>::new()));
+  }
+
+  #[test]
+  fn retained_by_lists_the_other_branches_tags_and_deployments_of_the_version() {
+    let version = json!({
+      "number": "1",
+      "_embedded": {
+        "branchVersions": [ { "name": "feat/x" }, { "name": "main" } ],
+        "tags": [ { "name": "prod" } ]
+      }
+    });
+    let deployments = hashmap!{ "1".to_string() => vec!["deployed to production".to_string()] };
+    expect!(retained_by(&version, "feat/x", &deployments)).to(be_equal_to(vec![
+      "branch main".to_string(), "tag prod".to_string(), "deployed to production".to_string()
+    ]));
+    expect!(retained_by(&json!({ "number": "2" }), "feat/x", &deployments)).to(be_equal_to(Vec::<String>::new()));
+  }
+}
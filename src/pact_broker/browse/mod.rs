@@ -0,0 +1,145 @@
+//! Interactive terminal UI for browsing the Pact Broker: the pacticipants with their versions,
+//! branches and environments, the pacts between them, the matrix of verification results and the
+//! interactions of each pact
+
+use std::io::IsTerminal;
+use std::ops::ControlFlow;
+
+use clap::ArgMatches;
+use crossterm::event::{Event, EventStream, KeyEventKind};
+use futures::future::{try_join_all, LocalBoxFuture};
+use futures::{FutureExt, StreamExt};
+use itertools::Itertools;
+use maplit::hashmap;
+use ratatui::DefaultTerminal;
+
+use crate::error::CliError;
+
+use super::{HALClient, Pagination, PactBrokerError};
+use super::environments::{currently_deployed_versions, fetch_environments, find_environment_by_name};
+use super::matrix::fetch_matrix;
+use super::pacticipants::fetch_pacticipant;
+use super::resources::Pacticipant;
+use super::utils::broker_client;
+use views::{App, Content, Screen, Source, Target};
+
+pub mod ui;
+pub mod views;
+
+/// Number of versions and branches displayed for a pacticipant, the most recent ones are displayed
+const MAX_ITEMS: usize = 100;
+
+async fn pacticipant_collection(hal_client: &HALClient, name: &str, relation: &str, embedded: &str) -> Result<Vec<serde_json::Value>, PactBrokerError> {
+  let pacticipant: Pacticipant = fetch_pacticipant(hal_client, name).await?.resource()?;
+  let url = pacticipant.links.href(relation)
+    .ok_or_else(|| PactBrokerError::LinkError(format!(
+      "Pacticipant '{}' has no '{}' link. Please upgrade to a later version of the Pact Broker", name, relation)))?;
+  hal_client.fetch_pages(url, embedded, &Pagination { page_size: Some(MAX_ITEMS), limit: Some(MAX_ITEMS) }).await
+}
+
+/// Loads the content of a tab from the Pact Broker. Errors are displayed in the tab.
+async fn load(hal_client: &HALClient, source: &Source) -> Content {
+  let result = async {
+    match source {
+      Source::Pacticipants => {
+        let url = hal_client.link_url("pb:pacticipants", &hashmap!{})?;
+        views::pacticipants_table(&hal_client.fetch_all_pages(url.as_str(), "pacticipants").await?).map(Content::Table)
+      }
+      Source::Versions(name) => {
+        let versions = pacticipant_collection(hal_client, name, "pb:versions", "versions").await?;
+        views::versions_table(name, &versions).map(Content::Table)
+      }
+      Source::Branches(name) => {
+        let branches = pacticipant_collection(hal_client, name, "pb:branches", "branches").await?;
+        views::branches_table(name, &branches).map(Content::Table)
+      }
+      Source::Environments(name) => {
+        let environments = fetch_environments(hal_client).await?;
+        let deployments = try_join_all(environments.iter()
+          .map(|environment| currently_deployed_versions(hal_client, environment, name))).await?;
+        let environments = environments.into_iter().zip(deployments)
+          .map(|(environment, (deployed, released))| (environment, deployed, released))
+          .collect_vec();
+        Ok(Content::Table(views::environments_table(name, &environments)))
+      }
+      Source::Pacts(name) => {
+        let url = hal_client.link_url("pb:latest-pact-versions", &hashmap!{})?;
+        views::pacts_table(&hal_client.fetch_all_pages(url.as_str(), "pacts").await?, name.as_deref()).map(Content::Table)
+      }
+      Source::Matrix(query) => {
+        if let Some(environment) = &query.to_environment {
+          find_environment_by_name(hal_client, environment).await?;
+        }
+        views::matrix_table(&fetch_matrix(hal_client, query, 0, 0).await?).map(Content::Table)
+      }
+      Source::Interactions(url) => Ok(Content::Table(views::interactions_table(&hal_client.clone().fetch(url).await?))),
+      Source::Resource(url) => Ok(views::document_text(&hal_client.clone().fetch(url).await?)),
+      Source::Document(document) => Ok(views::document_text(document))
+    }
+  }.await;
+  result.unwrap_or_else(|err: PactBrokerError| Content::Error(err.to_string()))
+}
+
+/// Runs the event loop. The content of the current tab is loaded while the key events are handled,
+/// and the load is dropped if the user moves to another tab before it has completed.
+async fn run(terminal: &mut DefaultTerminal, hal_client: &HALClient, app: &mut App) -> std::io::Result<()> {
+  let mut events = EventStream::new();
+  let mut loading: Option<(Source, LocalBoxFuture<'_, Content>)> = None;
+  loop {
+    let tab = app.current_tab_mut();
+    if tab.content.is_none() {
+      if loading.as_ref().map(|(source, _)| source != &tab.source).unwrap_or(true) {
+        let source = tab.source.clone();
+        loading = Some((source.clone(), async move { load(hal_client, &source).await }.boxed_local()));
+      }
+    } else {
+      loading = None;
+    }
+    terminal.draw(|frame| ui::render(frame, app))?;
+
+    let event = match &mut loading {
+      Some((_, content)) => tokio::select! {
+        content = content => {
+          app.current_tab_mut().content = Some(content);
+          loading = None;
+          continue;
+        }
+        event = events.next() => event
+      },
+      None => events.next().await
+    };
+    match event {
+      Some(Ok(Event::Key(key))) if key.kind == KeyEventKind::Press => {
+        if let ControlFlow::Break(_) = app.handle_key(key) {
+          return Ok(());
+        }
+      }
+      Some(Ok(_)) => {}
+      Some(Err(err)) => return Err(err),
+      None => return Ok(())
+    }
+  }
+}
+
+/// Handles the `pact-broker browse` command
+pub async fn browse(args: &ArgMatches) -> Result<(), CliError> {
+  if !std::io::stdout().is_terminal() {
+    return Err(CliError::InvalidArguments("The browse command needs an interactive terminal".to_string()));
+  }
+
+  let hal_client = broker_client(args)?.with_index().await
+    .map_err(|err| CliError::broker("Failed to load the index resource of the Pact Broker", err))?;
+  let screen = match args.get_one::<String>("pacticipant") {
+    Some(name) => {
+      fetch_pacticipant(&hal_client, name).await?;
+      Screen::open(Target::Pacticipant(name.clone()))
+    }
+    None => Screen::home()
+  };
+  let mut app = App::new(hal_client.url.as_str(), screen);
+
+  let mut terminal = ratatui::init();
+  let result = run(&mut terminal, &hal_client, &mut app).await;
+  ratatui::restore();
+  result.map_err(|err| CliError::Failed(format!("Failed to update the terminal - {}", err)))
+}
@@ -0,0 +1,116 @@
+//! Rendering of the `pact-broker browse` terminal UI
+
+use itertools::Itertools;
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Layout, Margin, Rect};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Paragraph, Row, Table, TableState, Tabs, Wrap};
+
+use super::views::{self, App, Content, Status, Tab};
+
+/// Widest a table column is allowed to be, longer values are truncated
+const MAX_COLUMN_WIDTH: usize = 60;
+
+fn status_style(status: Option<Status>) -> Style {
+  match status {
+    Some(Status::Success) => Style::default().fg(Color::Green),
+    Some(Status::Failure) => Style::default().fg(Color::Red),
+    Some(Status::Unknown) => Style::default().fg(Color::Yellow),
+    Some(Status::Inactive) => Style::default().fg(Color::DarkGray),
+    None => Style::default()
+  }
+}
+
+fn render_table(frame: &mut Frame, area: Rect, tab: &Tab, table: &views::Table) {
+  let rows = tab.visible_rows();
+  let widths = table.headers.iter().enumerate()
+    .map(|(i, header)| rows.iter()
+      .map(|row| row.cells.get(i).map(|cell| cell.chars().count()).unwrap_or_default())
+      .chain([header.len()])
+      .max()
+      .unwrap_or_default()
+      .min(MAX_COLUMN_WIDTH))
+    .map(|width| Constraint::Length(width as u16))
+    .collect_vec();
+  let header = Row::new(table.headers.clone()).style(Style::default().add_modifier(Modifier::BOLD));
+  let body = rows.iter()
+    .map(|row| Row::new(row.cells.clone()).style(status_style(row.status)))
+    .collect_vec();
+  let widget = Table::new(body, widths)
+    .header(header)
+    .column_spacing(2)
+    .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+    .block(Block::default().borders(Borders::TOP));
+
+  let [table_area, summary_area] = if table.summary.is_some() {
+    Layout::vertical([Constraint::Min(1), Constraint::Length(2)]).areas(area)
+  } else {
+    Layout::vertical([Constraint::Min(1), Constraint::Length(0)]).areas(area)
+  };
+  let mut state = TableState::default().with_selected(if rows.is_empty() { None } else { Some(tab.selected) });
+  frame.render_stateful_widget(widget, table_area, &mut state);
+  if rows.is_empty() {
+    let message = if tab.filter.is_empty() { "Nothing to show" } else { "No rows match the filter" };
+    frame.render_widget(Paragraph::new(message).dark_gray(), table_area.inner(Margin::new(0, 2)));
+  }
+  if let Some((summary, status)) = &table.summary {
+    frame.render_widget(Paragraph::new(summary.as_str()).style(status_style(Some(*status)))
+      .block(Block::default().borders(Borders::TOP)).wrap(Wrap { trim: true }), summary_area);
+  }
+}
+
+fn render_content(frame: &mut Frame, area: Rect, tab: &Tab) {
+  match &tab.content {
+    Some(Content::Table(table)) => render_table(frame, area, tab, table),
+    Some(Content::Text(lines)) => {
+      let text = lines.iter().skip(tab.scroll).map(|line| Line::raw(line.as_str())).collect_vec();
+      frame.render_widget(Paragraph::new(text).block(Block::default().borders(Borders::TOP)), area);
+    }
+    Some(Content::Error(message)) => frame.render_widget(Paragraph::new(message.as_str()).red()
+      .block(Block::default().borders(Borders::TOP)).wrap(Wrap { trim: false }), area),
+    None => frame.render_widget(Paragraph::new("Loading…").dark_gray()
+      .block(Block::default().borders(Borders::TOP)), area)
+  }
+}
+
+fn help_line(app: &App) -> Line<'_> {
+  let tab = app.screen().current_tab();
+  if app.filtering {
+    return Line::from(vec![Span::raw("Filter: ").bold(), Span::raw(tab.filter.as_str()), Span::raw("█")]);
+  }
+  let mut keys = vec![("↑↓", "move"), ("←→", "tabs"), ("enter", "open"), ("esc", "back"), ("/", "filter"),
+    ("r", "reload"), ("q", "quit")];
+  if app.screens.len() == 1 {
+    keys.retain(|(key, _)| *key != "esc");
+  }
+  let mut spans = keys.iter()
+    .flat_map(|(key, action)| [Span::raw(*key).bold(), Span::raw(format!(" {}  ", action)).dark_gray()])
+    .collect_vec();
+  if !tab.filter.is_empty() {
+    spans.push(Span::raw(format!("filter: {}", tab.filter)).yellow());
+  }
+  Line::from(spans)
+}
+
+/// Renders the displayed screen of the browser
+pub fn render(frame: &mut Frame, app: &App) {
+  let [header_area, tabs_area, content_area, help_area] = Layout::vertical([
+    Constraint::Length(1), Constraint::Length(1), Constraint::Min(3), Constraint::Length(1)
+  ]).areas(frame.area());
+
+  let breadcrumbs = app.screens.iter().map(|screen| screen.title.as_str()).join(" › ");
+  frame.render_widget(Line::from(vec![
+    Span::raw(breadcrumbs).bold(),
+    Span::raw(format!("  {}", app.broker_url)).dark_gray()
+  ]), header_area);
+
+  let screen = app.screen();
+  let tabs = Tabs::new(screen.tabs.iter().map(|tab| tab.title.as_str()))
+    .select(screen.tab)
+    .highlight_style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD | Modifier::UNDERLINED));
+  frame.render_widget(tabs, tabs_area);
+
+  render_content(frame, content_area, screen.current_tab());
+  frame.render_widget(help_line(app), help_area);
+}
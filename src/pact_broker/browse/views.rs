@@ -0,0 +1,644 @@
+//! State of the `pact-broker browse` terminal UI. The UI is a stack of screens, each with one or
+//! more tabs. A tab shows a table or a text document loaded from the Pact Broker, and selecting a
+//! row in a table opens the screen for its target (like a pacticipant, pact or matrix query).
+
+use std::ops::ControlFlow;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use itertools::Itertools;
+use serde_json::Value;
+
+use crate::pact_broker::matrix::{MatrixQuery, MatrixSelector};
+use crate::pact_broker::resources::{
+  from_json,
+  Branch,
+  DeployedVersion,
+  Environment,
+  HalResource,
+  MatrixRow,
+  Pacticipant,
+  PactVersion,
+  ReleasedVersion,
+  Version
+};
+use crate::pact_broker::utils::json_str;
+use crate::pact_broker::PactBrokerError;
+
+/// Number of rows or lines to move for the page up and page down keys
+const PAGE: usize = 10;
+
+/// Resource to open in a new screen
+#[derive(Debug, Clone, PartialEq)]
+pub enum Target {
+  /// Pacticipant, with its versions, branches, environments, pacts and matrix
+  Pacticipant(String),
+  /// Pact between a consumer and provider, with its interactions
+  Pact {
+    /// Consumer name
+    consumer: String,
+    /// Provider name
+    provider: String,
+    /// URL of the pact
+    url: String
+  },
+  /// Results of a matrix query
+  Matrix {
+    /// Screen title
+    title: String,
+    /// Query to run
+    query: MatrixQuery
+  },
+  /// Resource fetched from the Pact Broker, displayed as JSON
+  Resource {
+    /// Screen title
+    title: String,
+    /// URL of the resource
+    url: String
+  },
+  /// JSON document that has already been loaded
+  Document {
+    /// Screen title
+    title: String,
+    /// The document
+    document: Value
+  }
+}
+
+/// Where the content of a tab is loaded from
+#[derive(Debug, Clone, PartialEq)]
+pub enum Source {
+  /// All the pacticipants
+  Pacticipants,
+  /// Latest versions of the pacticipant
+  Versions(String),
+  /// Branches of the pacticipant
+  Branches(String),
+  /// Versions of the pacticipant deployed or released to each environment
+  Environments(String),
+  /// Latest pacts, for all pacticipants or only the ones the pacticipant is part of
+  Pacts(Option<String>),
+  /// Results of a matrix query
+  Matrix(MatrixQuery),
+  /// Interactions of the pact at the URL
+  Interactions(String),
+  /// Resource at the URL
+  Resource(String),
+  /// JSON document that has already been loaded
+  Document(Value)
+}
+
+/// Status of a row, used to colour it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+  /// Verified successfully, deployable or currently deployed
+  Success,
+  /// Verification failed or not deployable
+  Failure,
+  /// Not verified yet
+  Unknown,
+  /// Not deployed or released
+  Inactive
+}
+
+/// Row of a table
+#[derive(Debug, Clone, PartialEq)]
+pub struct Row {
+  /// Values for the columns
+  pub cells: Vec<String>,
+  /// Status of the row
+  pub status: Option<Status>,
+  /// Resource that is opened when the row is selected
+  pub target: Option<Target>
+}
+
+impl Row {
+  /// Row with the values, without a status or target
+  pub fn new<S: Into<String>>(cells: impl IntoIterator<Item = S>) -> Row {
+    Row { cells: cells.into_iter().map(|cell| cell.into()).collect(), status: None, target: None }
+  }
+
+  /// Sets the status of the row
+  pub fn with_status(self, status: Option<Status>) -> Row {
+    Row { status, .. self }
+  }
+
+  /// Sets the target of the row
+  pub fn with_target(self, target: Target) -> Row {
+    Row { target: Some(target), .. self }
+  }
+}
+
+/// Table of rows, with an optional summary line displayed below it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Table {
+  /// Column headers
+  pub headers: Vec<String>,
+  /// Rows
+  pub rows: Vec<Row>,
+  /// Summary of the table, with its status
+  pub summary: Option<(String, Status)>
+}
+
+impl Table {
+  fn new(headers: &[&str], rows: Vec<Row>) -> Table {
+    Table { headers: headers.iter().map(|header| header.to_string()).collect(), rows, summary: None }
+  }
+}
+
+/// Content of a tab
+#[derive(Debug, Clone, PartialEq)]
+pub enum Content {
+  /// Table of resources
+  Table(Table),
+  /// Lines of text
+  Text(Vec<String>),
+  /// Loading the content failed
+  Error(String)
+}
+
+/// Tab of a screen
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tab {
+  /// Title of the tab
+  pub title: String,
+  /// Where the content is loaded from
+  pub source: Source,
+  /// Loaded content, or `None` while it is being loaded
+  pub content: Option<Content>,
+  /// Selected row of the filtered table
+  pub selected: usize,
+  /// Scroll offset of the text
+  pub scroll: usize,
+  /// Only the table rows containing the filter are displayed
+  pub filter: String
+}
+
+impl Tab {
+  fn new(title: &str, source: Source) -> Tab {
+    Tab { title: title.to_string(), source, content: None, selected: 0, scroll: 0, filter: String::new() }
+  }
+
+  /// Rows of the table that match the filter (ignoring case)
+  pub fn visible_rows(&self) -> Vec<&Row> {
+    let filter = self.filter.to_lowercase();
+    match &self.content {
+      Some(Content::Table(table)) => table.rows.iter()
+        .filter(|row| filter.is_empty() || row.cells.iter().any(|cell| cell.to_lowercase().contains(&filter)))
+        .collect(),
+      _ => vec![]
+    }
+  }
+
+  /// The selected row
+  pub fn selected_row(&self) -> Option<&Row> {
+    self.visible_rows().get(self.selected).copied()
+  }
+
+  fn move_by(&mut self, delta: isize) {
+    match &self.content {
+      Some(Content::Table(_)) => {
+        let count = self.visible_rows().len();
+        self.selected = self.selected.saturating_add_signed(delta).min(count.saturating_sub(1));
+      }
+      Some(Content::Text(lines)) => {
+        self.scroll = self.scroll.saturating_add_signed(delta).min(lines.len().saturating_sub(1));
+      }
+      _ => {}
+    }
+  }
+}
+
+/// Screen of the UI, with its tabs
+#[derive(Debug, Clone, PartialEq)]
+pub struct Screen {
+  /// Title of the screen, displayed in the breadcrumbs
+  pub title: String,
+  /// Tabs of the screen
+  pub tabs: Vec<Tab>,
+  /// Index of the current tab
+  pub tab: usize
+}
+
+impl Screen {
+  /// First screen, with the pacticipants and the latest pacts
+  pub fn home() -> Screen {
+    Screen {
+      title: "Pact Broker".to_string(),
+      tabs: vec![Tab::new("Pacticipants", Source::Pacticipants), Tab::new("Pacts", Source::Pacts(None))],
+      tab: 0
+    }
+  }
+
+  /// Screen for the target
+  pub fn open(target: Target) -> Screen {
+    let (title, tabs) = match target {
+      Target::Pacticipant(name) => (name.clone(), vec![
+        Tab::new("Versions", Source::Versions(name.clone())),
+        Tab::new("Branches", Source::Branches(name.clone())),
+        Tab::new("Environments", Source::Environments(name.clone())),
+        Tab::new("Pacts", Source::Pacts(Some(name.clone()))),
+        Tab::new("Matrix", Source::Matrix(MatrixQuery {
+          selectors: vec![MatrixSelector { latest: true, .. MatrixSelector::new(&name) }],
+          .. MatrixQuery::default()
+        }))
+      ]),
+      Target::Pact { consumer, provider, url } => (format!("{} → {}", consumer, provider), vec![
+        Tab::new("Interactions", Source::Interactions(url.clone())),
+        Tab::new("Matrix", Source::Matrix(MatrixQuery {
+          selectors: vec![MatrixSelector::new(&consumer), MatrixSelector::new(&provider)],
+          .. MatrixQuery::default()
+        })),
+        Tab::new("JSON", Source::Resource(url))
+      ]),
+      Target::Matrix { title, query } => (title, vec![Tab::new("Matrix", Source::Matrix(query))]),
+      Target::Resource { title, url } => (title, vec![Tab::new("JSON", Source::Resource(url))]),
+      Target::Document { title, document } => (title, vec![Tab::new("JSON", Source::Document(document))])
+    };
+    Screen { title, tabs, tab: 0 }
+  }
+
+  /// The current tab
+  pub fn current_tab(&self) -> &Tab {
+    &self.tabs[self.tab]
+  }
+
+  /// The current tab, for updating
+  pub fn current_tab_mut(&mut self) -> &mut Tab {
+    &mut self.tabs[self.tab]
+  }
+}
+
+/// State of the browser
+#[derive(Debug, Clone, PartialEq)]
+pub struct App {
+  /// URL of the Pact Broker
+  pub broker_url: String,
+  /// Stack of screens, the last one is displayed
+  pub screens: Vec<Screen>,
+  /// If keys are being typed into the filter of the current tab
+  pub filtering: bool
+}
+
+impl App {
+  /// Browser starting with the screen
+  pub fn new(broker_url: &str, screen: Screen) -> App {
+    App { broker_url: broker_url.to_string(), screens: vec![screen], filtering: false }
+  }
+
+  /// The displayed screen
+  pub fn screen(&self) -> &Screen {
+    self.screens.last().expect("there is always at least one screen")
+  }
+
+  fn screen_mut(&mut self) -> &mut Screen {
+    self.screens.last_mut().expect("there is always at least one screen")
+  }
+
+  /// The current tab of the displayed screen
+  pub fn current_tab_mut(&mut self) -> &mut Tab {
+    self.screen_mut().current_tab_mut()
+  }
+
+  /// Updates the state for the key. Returns `ControlFlow::Break` when the user quits.
+  pub fn handle_key(&mut self, key: KeyEvent) -> ControlFlow<()> {
+    if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+      return ControlFlow::Break(());
+    }
+
+    if self.filtering {
+      let tab = self.current_tab_mut();
+      match key.code {
+        KeyCode::Char(ch) => tab.filter.push(ch),
+        KeyCode::Backspace => { tab.filter.pop(); }
+        KeyCode::Esc => {
+          tab.filter.clear();
+          self.filtering = false;
+        }
+        KeyCode::Enter => self.filtering = false,
+        _ => {}
+      }
+      self.current_tab_mut().selected = 0;
+      return ControlFlow::Continue(());
+    }
+
+    match key.code {
+      KeyCode::Char('q') => return ControlFlow::Break(()),
+      KeyCode::Up | KeyCode::Char('k') => self.current_tab_mut().move_by(-1),
+      KeyCode::Down | KeyCode::Char('j') => self.current_tab_mut().move_by(1),
+      KeyCode::PageUp => self.current_tab_mut().move_by(-(PAGE as isize)),
+      KeyCode::PageDown => self.current_tab_mut().move_by(PAGE as isize),
+      KeyCode::Home | KeyCode::Char('g') => self.current_tab_mut().move_by(isize::MIN),
+      KeyCode::End | KeyCode::Char('G') => self.current_tab_mut().move_by(isize::MAX),
+      KeyCode::Tab | KeyCode::Right | KeyCode::Char('l') => {
+        let screen = self.screen_mut();
+        screen.tab = (screen.tab + 1) % screen.tabs.len();
+      }
+      KeyCode::BackTab | KeyCode::Left | KeyCode::Char('h') => {
+        let screen = self.screen_mut();
+        screen.tab = (screen.tab + screen.tabs.len() - 1) % screen.tabs.len();
+      }
+      KeyCode::Enter => {
+        if let Some(target) = self.screen().current_tab().selected_row().and_then(|row| row.target.clone()) {
+          self.screens.push(Screen::open(target));
+        }
+      }
+      KeyCode::Esc | KeyCode::Backspace => {
+        if !self.current_tab_mut().filter.is_empty() {
+          self.current_tab_mut().filter.clear();
+        } else if self.screens.len() > 1 {
+          self.screens.pop();
+        }
+      }
+      KeyCode::Char('/') => self.filtering = true,
+      KeyCode::Char('r') => {
+        let tab = self.current_tab_mut();
+        tab.content = None;
+        tab.selected = 0;
+        tab.scroll = 0;
+      }
+      _ => {}
+    }
+    ControlFlow::Continue(())
+  }
+}
+
+/// Table of the pacticipants
+pub fn pacticipants_table(items: &[Value]) -> Result<Table, PactBrokerError> {
+  let rows = items.iter()
+    .map(|item| from_json::<Pacticipant>(item).map(|pacticipant| Row::new([
+        pacticipant.name.clone(),
+        pacticipant.display_name.unwrap_or_default(),
+        pacticipant.main_branch.unwrap_or_default(),
+        pacticipant.updated_at.or(pacticipant.created_at).unwrap_or_default()
+      ]).with_target(Target::Pacticipant(pacticipant.name))))
+    .collect::<Result<Vec<_>, _>>()?;
+  Ok(Table::new(&["NAME", "DISPLAY NAME", "MAIN BRANCH", "UPDATED AT"], rows))
+}
+
+/// Table of the versions of the pacticipant. Selecting a version opens the matrix of the version
+/// with the latest versions of its integrations.
+pub fn versions_table(pacticipant: &str, items: &[Value]) -> Result<Table, PactBrokerError> {
+  let rows = items.iter()
+    .map(|item| from_json::<Version>(item).map(|version| Row::new([
+        version.number.clone(),
+        version.branch_names().join(", "),
+        version.tag_names().join(", "),
+        version.created_at.clone().unwrap_or_default()
+      ]).with_target(Target::Matrix {
+        title: format!("{} {}", pacticipant, version.number),
+        query: MatrixQuery {
+          selectors: vec![MatrixSelector { version: Some(version.number), .. MatrixSelector::new(pacticipant) }],
+          .. MatrixQuery::default()
+        }
+      })))
+    .collect::<Result<Vec<_>, _>>()?;
+  Ok(Table::new(&["VERSION", "BRANCHES", "TAGS", "CREATED AT"], rows))
+}
+
+/// Table of the branches of the pacticipant. Selecting a branch opens the matrix of the latest
+/// version on the branch.
+pub fn branches_table(pacticipant: &str, items: &[Value]) -> Result<Table, PactBrokerError> {
+  let rows = items.iter()
+    .map(|item| from_json::<Branch>(item).map(|branch| Row::new([
+        branch.name.clone(),
+        branch.created_at.unwrap_or_default(),
+        branch.updated_at.unwrap_or_default()
+      ]).with_target(Target::Matrix {
+        title: format!("{} latest on {}", pacticipant, branch.name),
+        query: MatrixQuery {
+          selectors: vec![MatrixSelector { branch: Some(branch.name), latest: true, .. MatrixSelector::new(pacticipant) }],
+          .. MatrixQuery::default()
+        }
+      })))
+    .collect::<Result<Vec<_>, _>>()?;
+  Ok(Table::new(&["BRANCH", "CREATED AT", "UPDATED AT"], rows))
+}
+
+/// Table of the versions of the pacticipant currently deployed or released to each environment.
+/// Selecting a version opens the matrix of the version for the environment.
+pub fn environments_table(
+  pacticipant: &str,
+  environments: &[(Environment, Vec<DeployedVersion>, Vec<ReleasedVersion>)]
+) -> Table {
+  let mut rows = vec![];
+  for (environment, deployed_versions, released_versions) in environments {
+    let versions = deployed_versions.iter()
+      .map(|deployed| (deployed.version_number(), "deployed", deployed.instance(), deployed.created_at.as_deref()))
+      .chain(released_versions.iter()
+        .map(|released| (released.embedded.version.as_ref().map(|version| version.number.as_str()), "released",
+          None, released.created_at.as_deref())))
+      .collect_vec();
+    if versions.is_empty() {
+      rows.push(Row::new([environment.name.as_str(), "", "not deployed", "", ""]).with_status(Some(Status::Inactive)));
+    }
+    for (version, kind, instance, since) in versions {
+      let version = version.unwrap_or_default();
+      rows.push(Row::new([environment.name.as_str(), version, kind, instance.unwrap_or_default(), since.unwrap_or_default()])
+        .with_status(Some(Status::Success))
+        .with_target(Target::Matrix {
+          title: format!("{} {} in {}", pacticipant, version, environment.name),
+          query: MatrixQuery {
+            selectors: vec![MatrixSelector { version: Some(version.to_string()), .. MatrixSelector::new(pacticipant) }],
+            to_environment: Some(environment.name.clone()),
+            .. MatrixQuery::default()
+          }
+        }));
+    }
+  }
+  Table::new(&["ENVIRONMENT", "VERSION", "STATUS", "INSTANCE", "SINCE"], rows)
+}
+
+/// Table of the pacts, only including the pacts the pacticipant is the consumer or provider of if
+/// a pacticipant is given
+pub fn pacts_table(items: &[Value], pacticipant: Option<&str>) -> Result<Table, PactBrokerError> {
+  let rows = items.iter()
+    .map(from_json::<PactVersion>)
+    .filter_ok(|pact| pacticipant.map(|name| pact.embedded.consumer.name == name || pact.embedded.provider.name == name).unwrap_or(true))
+    .map_ok(|pact| {
+      let consumer = pact.embedded.consumer.name.clone();
+      let provider = pact.embedded.provider.name.clone();
+      let row = Row::new([
+        consumer.clone(),
+        pact.consumer_version().unwrap_or_default().to_string(),
+        provider.clone(),
+        pact.created_at.clone().unwrap_or_default()
+      ]);
+      match pact.self_url() {
+        Some(url) => row.with_target(Target::Pact { consumer, provider, url: url.to_string() }),
+        None => row
+      }
+    })
+    .collect::<Result<Vec<_>, _>>()?;
+  Ok(Table::new(&["CONSUMER", "CONSUMER VERSION", "PROVIDER", "PUBLISHED AT"], rows))
+}
+
+/// Table of the rows of the matrix, with the verification status of each and the broker's verdict
+/// as the summary. Selecting a verified row opens the verification result.
+pub fn matrix_table(matrix: &Value) -> Result<Table, PactBrokerError> {
+  let rows = match matrix.get("matrix") {
+    Some(rows) => from_json::<Vec<MatrixRow>>(rows)?,
+    None => vec![]
+  };
+  let rows = rows.iter()
+    .map(|row| {
+      let result = row.verification_result.as_ref();
+      let (success, status) = match result.and_then(|result| result.success) {
+        Some(true) => ("true", Status::Success),
+        Some(false) => ("false", Status::Failure),
+        None => ("???", Status::Unknown)
+      };
+      let table_row = Row::new([
+        row.consumer.name.as_str(),
+        row.consumer_version(),
+        row.provider.name.as_str(),
+        row.provider_version(),
+        success,
+        result.and_then(|result| result.verified_at.as_deref()).unwrap_or_default()
+      ]).with_status(Some(status));
+      match result.and_then(|result| result.self_url()) {
+        Some(url) => table_row.with_target(Target::Resource {
+          title: format!("Verification of {} {} by {} {}", row.consumer.name, row.consumer_version(),
+            row.provider.name, row.provider_version()),
+          url: url.to_string()
+        }),
+        None => table_row
+      }
+    })
+    .collect();
+  let mut table = Table::new(&["CONSUMER", "C.VERSION", "PROVIDER", "P.VERSION", "SUCCESS?", "VERIFIED AT"], rows);
+  if let Some(summary) = matrix.get("summary") {
+    let deployable = summary.get("deployable").and_then(|deployable| deployable.as_bool());
+    let status = match deployable {
+      Some(true) => Status::Success,
+      Some(false) => Status::Failure,
+      None => Status::Unknown
+    };
+    table.summary = Some((json_str(summary, "/reason").to_string(), status));
+  }
+  Ok(table)
+}
+
+/// Provider states of the interaction, from the `providerStates` (V3+) or `providerState` (V2) attribute
+fn provider_states(interaction: &Value) -> String {
+  match interaction.get("providerStates") {
+    Some(Value::Array(states)) => states.iter().map(|state| json_str(state, "/name")).join(", "),
+    _ => json_str(interaction, "/providerState").to_string()
+  }
+}
+
+/// Kind of interaction, with the request method and path for HTTP interactions
+fn interaction_kind(interaction: &Value) -> String {
+  match interaction.get("request") {
+    Some(request) => format!("{} {}", json_str(request, "/method").to_uppercase(), json_str(request, "/path")),
+    None => match interaction.get("type").and_then(|kind| kind.as_str()) {
+      Some(kind) => kind.to_string(),
+      None => "Message".to_string()
+    }
+  }
+}
+
+/// Table of the interactions and messages of the pact. Selecting an interaction opens its JSON.
+pub fn interactions_table(pact: &Value) -> Table {
+  let rows = ["interactions", "messages"].iter()
+    .flat_map(|key| pact.get(*key).and_then(|items| items.as_array()).cloned().unwrap_or_default())
+    .map(|interaction| {
+      let description = json_str(&interaction, "/description").to_string();
+      Row::new([description.clone(), interaction_kind(&interaction), provider_states(&interaction)])
+        .with_target(Target::Document { title: description, document: interaction })
+    })
+    .collect();
+  Table::new(&["DESCRIPTION", "REQUEST", "PROVIDER STATES"], rows)
+}
+
+/// Lines of the JSON document, pretty printed
+pub fn document_text(document: &Value) -> Content {
+  Content::Text(serde_json::to_string_pretty(document).unwrap_or_default().lines().map(|line| line.to_string()).collect())
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use super::*;
+
+  fn key(code: KeyCode) -> KeyEvent {
+    KeyEvent::new(code, KeyModifiers::NONE)
+  }
+
+  fn loaded(screen: Screen, table: Table) -> Screen {
+    let mut screen = screen;
+    screen.current_tab_mut().content = Some(Content::Table(table));
+    screen
+  }
+
+  #[test]
+  fn keys_filter_the_rows_and_open_and_close_screens() {
+    let pacticipants = pacticipants_table(&[json!({ "name": "Foo" }), json!({ "name": "Bar" })]).unwrap();
+    let mut app = App::new("http://localhost", loaded(Screen::home(), pacticipants));
+
+    for code in [KeyCode::Char('/'), KeyCode::Char('b'), KeyCode::Char('A'), KeyCode::Enter] {
+      expect!(app.handle_key(key(code))).to(be_equal_to(ControlFlow::Continue(())));
+    }
+    expect!(app.screen().current_tab().visible_rows().len()).to(be_equal_to(1));
+
+    let _ = app.handle_key(key(KeyCode::Enter));
+    expect!(app.screens.len()).to(be_equal_to(2));
+    expect!(app.screen().title.as_str()).to(be_equal_to("Bar"));
+    expect!(app.screen().tabs.iter().map(|tab| tab.title.as_str()).collect_vec())
+      .to(be_equal_to(vec!["Versions", "Branches", "Environments", "Pacts", "Matrix"]));
+
+    let _ = app.handle_key(key(KeyCode::Left));
+    expect!(app.screen().tab).to(be_equal_to(4));
+    let _ = app.handle_key(key(KeyCode::Esc));
+    expect!(app.screens.len()).to(be_equal_to(1));
+    let _ = app.handle_key(key(KeyCode::Esc));
+    expect!(app.screen().current_tab().visible_rows().len()).to(be_equal_to(2));
+    expect!(app.handle_key(key(KeyCode::Char('q')))).to(be_equal_to(ControlFlow::Break(())));
+  }
+
+  #[test]
+  fn matrix_table_shows_the_verification_status() {
+    let matrix = json!({
+      "summary": { "deployable": false, "reason": "One or more verifications have failed" },
+      "matrix": [
+        {
+          "consumer": { "name": "Foo", "version": { "number": "1" } },
+          "provider": { "name": "Bar", "version": { "number": "2" } },
+          "verificationResult": { "success": false, "verifiedAt": "2024-01-01", "_links": { "self": { "href": "http://localhost/verification" } } }
+        },
+        {
+          "consumer": { "name": "Foo", "version": { "number": "1" } },
+          "provider": { "name": "Baz" }
+        }
+      ]
+    });
+    let table = matrix_table(&matrix).unwrap();
+    expect!(table.rows.iter().map(|row| (row.cells[4].as_str(), row.status)).collect_vec()).to(be_equal_to(vec![
+      ("false", Some(Status::Failure)),
+      ("???", Some(Status::Unknown))
+    ]));
+    expect!(table.rows[0].target.clone()).to(be_some().value(Target::Resource {
+      title: "Verification of Foo 1 by Bar 2".to_string(),
+      url: "http://localhost/verification".to_string()
+    }));
+    expect!(table.rows[1].target.clone()).to(be_none());
+    expect!(table.summary).to(be_some().value(("One or more verifications have failed".to_string(), Status::Failure)));
+  }
+
+  #[test]
+  fn interactions_table_lists_http_interactions_and_messages() {
+    let pact = json!({
+      "interactions": [{
+        "description": "a request for Foo",
+        "providerStates": [{ "name": "Foo exists" }],
+        "request": { "method": "get", "path": "/foo" }
+      }],
+      "messages": [{ "description": "a Foo event", "providerState": "Foo was created" }]
+    });
+    expect!(interactions_table(&pact).rows.iter().map(|row| row.cells.clone()).collect_vec()).to(be_equal_to(vec![
+      vec!["a request for Foo".to_string(), "GET /foo".to_string(), "Foo exists".to_string()],
+      vec!["a Foo event".to_string(), "Message".to_string(), "Foo was created".to_string()]
+    ]));
+  }
+}
@@ -0,0 +1,265 @@
+//! On-disk cache of the responses from the Pact Broker. Responses are stored with their `ETag` and
+//! `Last-Modified` headers, so they can be revalidated with conditional requests. In offline mode,
+//! the cached responses are returned without contacting the Pact Broker.
+//!
+//! The responses can contain private data, so the cache directory is only accessible by the user
+//! (on Unix). Responses that have not been used for `MAX_AGE` are removed, as are the least recently
+//! used ones once there are more than `MAX_ENTRIES`.
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Once;
+use std::time::{Duration, SystemTime};
+
+use pact_models::http_utils::HttpAuth;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use tracing::{debug, warn};
+
+/// Cached responses that have not been used for this long are removed
+const MAX_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// Maximum number of cached responses
+const MAX_ENTRIES: usize = 1000;
+
+/// The cache is only pruned on the first store of each run
+static PRUNE: Once = Once::new();
+
+/// Response from the Pact Broker stored in the cache
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CachedResponse {
+  /// URL of the resource
+  pub url: String,
+  /// Value of the `ETag` header of the response
+  pub etag: Option<String>,
+  /// Value of the `Last-Modified` header of the response
+  pub last_modified: Option<String>,
+  /// Response body
+  pub body: Value
+}
+
+/// Cache of the responses from the Pact Broker, keyed by the URL and the credentials used to fetch
+/// them. The credentials are only stored as part of a hash.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResponseCache {
+  dir: PathBuf,
+  offline: bool
+}
+
+impl ResponseCache {
+  /// Cache that stores the responses in the directory
+  pub fn new<P: Into<PathBuf>>(dir: P) -> ResponseCache {
+    ResponseCache { dir: dir.into(), offline: false }
+  }
+
+  /// Default location of the cache, `~/.cache/pact/broker`
+  pub fn default_dir() -> Option<PathBuf> {
+    home::home_dir().map(|home| home.join(".cache").join("pact").join("broker"))
+  }
+
+  /// Returns the cache in offline mode, where the cached responses are used without contacting the
+  /// Pact Broker
+  pub fn offline(self, offline: bool) -> ResponseCache {
+    ResponseCache { offline, .. self }
+  }
+
+  /// If the cache is in offline mode
+  pub fn is_offline(&self) -> bool {
+    self.offline
+  }
+
+  /// Returns the key for the URL and credentials
+  pub fn key(url: &str, auth: &Option<HttpAuth>) -> String {
+    let identity = match auth {
+      Some(HttpAuth::User(username, password)) => format!("basic:{}:{}", username, password.clone().unwrap_or_default()),
+      Some(HttpAuth::Token(token)) => format!("token:{}", token),
+      _ => String::new()
+    };
+    let mut hasher = Sha256::new();
+    hasher.update(url.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(identity.as_bytes());
+    hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect()
+  }
+
+  fn path(&self, key: &str) -> PathBuf {
+    self.dir.join(format!("{}.json", key))
+  }
+
+  /// Loads the cached response for the key, if there is one
+  pub fn load(&self, key: &str) -> Option<CachedResponse> {
+    let path = self.path(key);
+    let contents = fs::read_to_string(&path).ok()?;
+    match serde_json::from_str(&contents) {
+      Ok(response) => Some(response),
+      Err(err) => {
+        warn!("Ignoring the invalid cached response {} - {}", path.display(), err);
+        None
+      }
+    }
+  }
+
+  /// Stores the response for the key. The first store of each run also removes the expired
+  /// responses. Failures to write to the cache are logged and ignored.
+  pub fn store(&self, key: &str, response: &CachedResponse) {
+    let path = self.path(key);
+    let temp_path = self.dir.join(format!("{}.tmp", key));
+    let result = create_private_dir(&self.dir)
+      .and_then(|_| write_private_file(&temp_path, serde_json::to_string(response).unwrap_or_default().as_str()))
+      .and_then(|_| fs::rename(&temp_path, &path));
+    match result {
+      Ok(_) => debug!("Cached the response from {} in {}", response.url, path.display()),
+      Err(err) => warn!("Failed to cache the response from {} in {} - {}", response.url, path.display(), err)
+    }
+    PRUNE.call_once(|| self.prune(MAX_AGE, MAX_ENTRIES));
+  }
+
+  /// Removes the cached responses older than the maximum age, and the oldest responses once there
+  /// are more than the maximum number of entries
+  fn prune(&self, max_age: Duration, max_entries: usize) {
+    let Ok(entries) = fs::read_dir(&self.dir) else {
+      return;
+    };
+    let mut responses = entries.flatten()
+      .filter(|entry| entry.path().extension().is_some_and(|extension| extension == "json"))
+      .filter_map(|entry| entry.metadata().and_then(|metadata| metadata.modified()).ok()
+        .map(|modified| (entry.path(), modified)))
+      .collect::<Vec<_>>();
+    responses.sort_by(|(_, a), (_, b)| b.cmp(a));
+
+    let now = SystemTime::now();
+    for (index, (path, modified)) in responses.iter().enumerate() {
+      let expired = now.duration_since(*modified).is_ok_and(|age| age > max_age);
+      if expired || index >= max_entries {
+        match fs::remove_file(path) {
+          Ok(_) => debug!("Removed the cached response {}", path.display()),
+          Err(err) => warn!("Failed to remove the cached response {} - {}", path.display(), err)
+        }
+      }
+    }
+  }
+}
+
+/// Creates the directory, only accessible by the user
+fn create_private_dir(dir: &Path) -> io::Result<()> {
+  #[cfg(unix)]
+  {
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+    fs::DirBuilder::new().recursive(true).mode(0o700).create(dir)?;
+    fs::set_permissions(dir, fs::Permissions::from_mode(0o700))
+  }
+  #[cfg(not(unix))]
+  {
+    fs::create_dir_all(dir)
+  }
+}
+
+/// Writes the file, only readable and writable by the user
+fn write_private_file(path: &Path, contents: &str) -> io::Result<()> {
+  let _ = fs::remove_file(path);
+  let mut options = fs::OpenOptions::new();
+  options.write(true).create_new(true);
+  #[cfg(unix)]
+  {
+    use std::os::unix::fs::OpenOptionsExt;
+    options.mode(0o600);
+  }
+  options.open(path)?.write_all(contents.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use super::*;
+  use super::super::{HALClient, RetryPolicy};
+
+  #[test]
+  fn key_depends_on_the_url_and_credentials() {
+    let auth = Some(HttpAuth::Token("1234".to_string()));
+    let key = ResponseCache::key("http://localhost/", &auth);
+    expect!(key.len()).to(be_equal_to(64));
+    expect!(key.clone()).to(be_equal_to(ResponseCache::key("http://localhost/", &auth)));
+    expect!(key.clone()).to_not(be_equal_to(ResponseCache::key("http://localhost/", &None)));
+    expect!(key.contains("1234")).to(be_false());
+    expect!(key).to_not(be_equal_to(ResponseCache::key("http://localhost/pacticipants", &auth)));
+  }
+
+  #[test]
+  fn stores_and_loads_responses() {
+    let dir = std::env::temp_dir().join(format!("pact_cli_cache_test_{}", std::process::id()));
+    let cache = ResponseCache::new(&dir);
+    let response = CachedResponse {
+      url: "http://localhost/".to_string(),
+      etag: Some("\"abc\"".to_string()),
+      last_modified: None,
+      body: json!({ "_links": {} })
+    };
+    expect!(cache.load("key")).to(be_none());
+    cache.store("key", &response);
+    expect!(cache.load("key")).to(be_some().value(response));
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn cache_files_are_only_accessible_by_the_user() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = std::env::temp_dir().join(format!("pact_cli_cache_permissions_test_{}", std::process::id()));
+    let cache = ResponseCache::new(&dir);
+    cache.store("key", &CachedResponse { url: "http://localhost/".to_string(), etag: None, last_modified: None, body: json!({}) });
+    let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+    expect!(mode(&dir)).to(be_equal_to(0o700));
+    expect!(mode(&dir.join("key.json"))).to(be_equal_to(0o600));
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn prune_removes_the_oldest_responses_over_the_maximum() {
+    let dir = std::env::temp_dir().join(format!("pact_cli_cache_prune_test_{}", std::process::id()));
+    let cache = ResponseCache::new(&dir);
+    let response = CachedResponse { url: "http://localhost/".to_string(), etag: None, last_modified: None, body: json!({}) };
+    for key in ["a", "b", "c"] {
+      cache.store(key, &response);
+      std::thread::sleep(Duration::from_millis(20));
+    }
+
+    cache.prune(MAX_AGE, 2);
+    expect!(cache.load("a")).to(be_none());
+    expect!(cache.load("b")).to(be_some());
+    expect!(cache.load("c")).to(be_some());
+
+    cache.prune(Duration::ZERO, MAX_ENTRIES);
+    expect!(cache.load("b")).to(be_none());
+    expect!(cache.load("c")).to(be_none());
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[tokio::test]
+  async fn fetch_only_uses_the_cached_response_in_offline_mode() {
+    let dir = std::env::temp_dir().join(format!("pact_cli_cache_offline_test_{}", std::process::id()));
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    drop(listener);
+    let cache = ResponseCache::new(&dir);
+    cache.store(&ResponseCache::key(format!("{}/pacticipants", url).as_str(), &None), &CachedResponse {
+      url: format!("{}/pacticipants", url),
+      etag: Some("\"1\"".to_string()),
+      last_modified: None,
+      body: json!({ "cached": true })
+    });
+    let client = HALClient::with_url(url.as_str(), None)
+      .with_cache(Some(cache))
+      .with_retry_policy(RetryPolicy { max_retries: 0, .. RetryPolicy::default() });
+
+    expect!(client.clone().fetch("/pacticipants").await).to(be_err());
+    let client = client.with_cache(Some(ResponseCache::new(&dir).offline(true)));
+    expect!(client.fetch("/pacticipants").await.map_err(|err| err.to_string())).to(be_ok().value(json!({ "cached": true })));
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+}
@@ -0,0 +1,126 @@
+//! Named profiles for connecting to Pact Brokers, loaded from `~/.config/pact/config.toml`
+//!
+//! ```toml
+//! default_profile = "oss"
+//!
+//! [profiles.oss]
+//! broker_base_url = "http://localhost:9292"
+//! broker_username = "pact"
+//! broker_password = "secret"
+//!
+//! [profiles.pactflow]
+//! broker_base_url = "https://example.pactflow.io"
+//! broker_token = "xxxx"
+//! ```
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use clap::ArgMatches;
+use serde::Deserialize;
+
+/// Connection details for a Pact Broker
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct BrokerProfile {
+  /// Base URL of the Pact Broker
+  pub broker_base_url: Option<String>,
+  /// Bearer token
+  pub broker_token: Option<String>,
+  /// Basic auth username
+  pub broker_username: Option<String>,
+  /// Basic auth password
+  pub broker_password: Option<String>
+}
+
+/// Contents of the config file
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct Config {
+  /// Profile to use when one is not given with `--profile`
+  pub default_profile: Option<String>,
+  /// Profiles by name
+  #[serde(default)]
+  pub profiles: BTreeMap<String, BrokerProfile>
+}
+
+impl Config {
+  /// Parses the config from TOML
+  pub fn from_toml(toml: &str) -> anyhow::Result<Config> {
+    toml::from_str(toml).map_err(|err| anyhow::anyhow!("Failed to parse the config file - {}", err))
+  }
+
+  /// Loads the config file, returning `None` if there is no config file
+  pub fn load() -> anyhow::Result<Option<Config>> {
+    match config_path() {
+      Some(path) if path.exists() => {
+        let contents = fs::read_to_string(&path)
+          .map_err(|err| anyhow::anyhow!("Failed to read the config file {} - {}", path.display(), err))?;
+        Config::from_toml(&contents)
+          .map(Some)
+          .map_err(|err| anyhow::anyhow!("{} ({})", err, path.display()))
+      }
+      _ => Ok(None)
+    }
+  }
+
+  /// Returns the named profile, or the default profile if no name is given
+  pub fn profile(&self, name: Option<&str>) -> anyhow::Result<Option<&BrokerProfile>> {
+    match name.or(self.default_profile.as_deref()) {
+      Some(name) => self.profiles.get(name)
+        .map(Some)
+        .ok_or_else(|| anyhow::anyhow!("No profile named '{}' was found in the config file. Available profiles: {}",
+          name, self.profiles.keys().cloned().collect::<Vec<_>>().join(", "))),
+      None => Ok(None)
+    }
+  }
+}
+
+/// Path to the config file (`~/.config/pact/config.toml`)
+pub fn config_path() -> Option<PathBuf> {
+  home::home_dir().map(|home| home.join(".config").join("pact").join("config.toml"))
+}
+
+/// Returns the profile selected with `--profile` (or the `PACT_BROKER_PROFILE` environment
+/// variable), falling back to the default profile from the config file
+pub fn selected_profile(args: &ArgMatches) -> anyhow::Result<Option<BrokerProfile>> {
+  let name = args.try_get_one::<String>("profile").ok().flatten();
+  match Config::load()? {
+    Some(config) => config.profile(name.map(|name| name.as_str())).map(|profile| profile.cloned()),
+    None => match name {
+      Some(name) => Err(anyhow::anyhow!("Profile '{}' was requested, but there is no config file at {}", name,
+        config_path().map(|path| path.display().to_string()).unwrap_or_else(|| "~/.config/pact/config.toml".to_string()))),
+      None => Ok(None)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+
+  use super::*;
+
+  #[test]
+  fn profile_returns_the_named_or_default_profile() {
+    let config = Config::from_toml(r#"
+      default_profile = "oss"
+
+      [profiles.oss]
+      broker_base_url = "http://localhost:9292"
+
+      [profiles.pactflow]
+      broker_base_url = "https://example.pactflow.io"
+      broker_token = "xxxx"
+    "#).unwrap();
+
+    expect!(config.profile(None).unwrap().and_then(|p| p.broker_base_url.clone()))
+      .to(be_some().value("http://localhost:9292"));
+    expect!(config.profile(Some("pactflow")).unwrap().cloned()).to(be_some().value(BrokerProfile {
+      broker_base_url: Some("https://example.pactflow.io".to_string()),
+      broker_token: Some("xxxx".to_string()),
+      .. BrokerProfile::default()
+    }));
+    expect!(config.profile(Some("onprem")).map_err(|err| err.to_string())).to(be_err().value(
+      "No profile named 'onprem' was found in the config file. Available profiles: oss, pactflow".to_string()));
+  }
+}
@@ -0,0 +1,156 @@
+//! Functions for recording deployments of pacticipant versions to environments
+
+use ansi_term::Colour::*;
+use clap::ArgMatches;
+use itertools::Itertools;
+use maplit::hashmap;
+use serde_json::{json, Value};
+
+use crate::error::CliError;
+
+use super::{HALClient, PactBrokerError};
+use super::environments::find_environment_by_name;
+use super::resources::{embedded_items, DeployedVersion, HalResource};
+use super::utils::{broker_client, json_str};
+
+fn application_instance_description(application_instance: Option<&str>) -> String {
+  application_instance
+    .map(|instance| format!(" (application instance {})", instance))
+    .unwrap_or_default()
+}
+
+/// Fetches the pacticipant version resource using the `pb:pacticipant-version` relation
+pub async fn fetch_pacticipant_version(
+  hal_client: &HALClient,
+  pacticipant: &str,
+  version: &str
+) -> Result<HALClient, PactBrokerError> {
+  hal_client.clone()
+    .navigate("pb:pacticipant-version", &hashmap!{
+      "pacticipant".to_string() => pacticipant.to_string(),
+      "version".to_string() => version.to_string()
+    })
+    .await
+    .map_err(|err| match err {
+      PactBrokerError::NotFound(_) => PactBrokerError::NotFound(
+        format!("Version {} of pacticipant '{}' does not exist in the Pact Broker", version, pacticipant)),
+      _ => err
+    })
+}
+
+/// Finds the link for the environment from the links with the given relation. The version resource
+/// has one link per environment, named with the environment name.
+pub fn find_environment_link(version: &Value, relation: &str, environment: &str) -> Result<String, PactBrokerError> {
+  let links = version.pointer(format!("/_links/{}", relation).as_str())
+    .and_then(|links| links.as_array())
+    .cloned()
+    .unwrap_or_default();
+  links.iter()
+    .find(|link| json_str(link, "/name") == environment)
+    .map(|link| json_str(link, "/href").to_string())
+    .ok_or_else(|| PactBrokerError::NotFound(format!("No environment found with name '{}'. Available options: {}",
+      environment, links.iter().map(|link| json_str(link, "/name")).join(", "))))
+}
+
+/// Handles the `pact-broker record-deployment` command
+pub async fn record_deployment(args: &ArgMatches) -> Result<(), CliError> {
+  let pacticipant = args.get_one::<String>("pacticipant").cloned().unwrap_or_default();
+  let version = args.get_one::<String>("version").cloned().unwrap_or_default();
+  let environment = args.get_one::<String>("environment").cloned().unwrap_or_default();
+  let application_instance = args.get_one::<String>("application-instance").cloned();
+  let hal_client = broker_client(args)?;
+
+  let result = async {
+    let version_client = fetch_pacticipant_version(&hal_client, &pacticipant, &version).await?;
+    let version_resource = version_client.path_info.clone().unwrap_or_default();
+    let url = find_environment_link(&version_resource, "pb:record-deployment", &environment)?;
+    let payload = match &application_instance {
+      Some(instance) => json!({ "applicationInstance": instance }),
+      None => json!({})
+    };
+    version_client.post_json(url.as_str(), payload.to_string().as_str()).await
+  }.await;
+
+  match result {
+    Ok(_) => {
+      println!("Recorded deployment of {} version {} to {} environment{} in the Pact Broker.",
+        pacticipant, version, environment, application_instance_description(application_instance.as_deref()));
+      Ok(())
+    }
+    Err(err) => Err(err.into())
+  }
+}
+
+/// Handles the `pact-broker record-undeployment` command. All the currently deployed versions of
+/// the pacticipant for the environment and application instance are marked as undeployed.
+pub async fn record_undeployment(args: &ArgMatches) -> Result<(), CliError> {
+  let pacticipant = args.get_one::<String>("pacticipant").cloned().unwrap_or_default();
+  let environment = args.get_one::<String>("environment").cloned().unwrap_or_default();
+  let application_instance = args.get_one::<String>("application-instance")
+    .or_else(|| args.get_one::<String>("target"))
+    .cloned();
+  let hal_client = broker_client(args)?;
+
+  let result = async {
+    let hal_client = hal_client.with_index().await?;
+    let environment_resource = find_environment_by_name(&hal_client, &environment).await?;
+    let deployed_versions_url = environment_resource.links.href("pb:currently-deployed-deployed-versions")
+      .ok_or_else(|| PactBrokerError::LinkError(format!(
+        "Environment '{}' has no 'pb:currently-deployed-deployed-versions' link. Please upgrade to Pact Broker version 2.80.0 or later", environment)))?;
+    let deployed_versions = hal_client.clone()
+      .fetch(format!("{}?pacticipant={}", deployed_versions_url, urlencoding::encode(&pacticipant)).as_str())
+      .await?;
+    let deployed_versions = embedded_items::<DeployedVersion>(&deployed_versions, "deployedVersions")?
+      .into_iter()
+      .filter(|deployed_version| deployed_version.instance() == application_instance.as_deref())
+      .collect_vec();
+
+    if deployed_versions.is_empty() {
+      return Err(PactBrokerError::NotFound(format!("{} is not currently deployed to {} environment{}. Cannot record undeployment.",
+        pacticipant, environment, application_instance_description(application_instance.as_deref()))));
+    }
+
+    let mut undeployed = vec![];
+    for deployed_version in deployed_versions {
+      let url = deployed_version.self_url().unwrap_or_default();
+      hal_client.patch_json(url, json!({ "currentlyDeployed": false }).to_string().as_str()).await?;
+      undeployed.push(deployed_version.version_number().unwrap_or_default().to_string());
+    }
+    Ok(undeployed)
+  }.await;
+
+  match result {
+    Ok(versions) => {
+      for version in versions {
+        println!("{}", Green.paint(format!("Recorded undeployment of {} version {} from {} environment{} in the Pact Broker.",
+          pacticipant, version, environment, application_instance_description(application_instance.as_deref()))));
+      }
+      Ok(())
+    }
+    Err(err) => Err(err.into())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use super::*;
+
+  #[test]
+  fn find_environment_link_returns_the_link_named_for_the_environment() {
+    let version = json!({
+      "_links": {
+        "pb:record-deployment": [
+          { "name": "test", "href": "http://localhost/deployed-versions/environment/1" },
+          { "name": "production", "href": "http://localhost/deployed-versions/environment/2" }
+        ]
+      }
+    });
+    expect!(find_environment_link(&version, "pb:record-deployment", "production"))
+      .to(be_ok().value("http://localhost/deployed-versions/environment/2".to_string()));
+    expect!(find_environment_link(&version, "pb:record-deployment", "uat"))
+      .to(be_err().value("No environment found with name 'uat'. Available options: test, production"));
+  }
+}
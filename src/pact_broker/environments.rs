@@ -0,0 +1,300 @@
+//! Functions for managing environments using the `pb:environments` relation
+
+use std::io::{self, BufRead, Write};
+
+use clap::ArgMatches;
+use maplit::hashmap;
+use serde_json::{json, Value};
+
+use crate::error::CliError;
+
+use super::{HALClient, PactBrokerError};
+use super::output::{print_output, OutputFormat, OutputOptions, ResourceTable, TableRow};
+use super::resources::{embedded_items, from_json, DeployedVersion, Environment, ReleasedVersion};
+use super::utils::{broker_client, json_str, pagination};
+
+/// Builds the environment resource from the command arguments. Any attributes not provided are
+/// taken from the existing resource (if there is one).
+pub fn environment_payload(args: &ArgMatches, existing: Option<&Value>) -> Value {
+  let mut payload = match existing {
+    Some(existing) => json!({
+      "name": existing.get("name"),
+      "displayName": existing.get("displayName"),
+      "production": existing.get("production"),
+      "contacts": existing.get("contacts")
+    }),
+    None => json!({ "production": false })
+  };
+
+  if let Some(name) = args.get_one::<String>("name") {
+    payload["name"] = json!(name);
+  }
+  if let Some(display_name) = args.get_one::<String>("display-name") {
+    payload["displayName"] = json!(display_name);
+  }
+  if args.get_flag("production") {
+    payload["production"] = json!(true);
+  } else if args.get_flag("no-production") {
+    payload["production"] = json!(false);
+  }
+
+  let contact_name = args.get_one::<String>("contact-name");
+  let contact_email = args.get_one::<String>("contact-email-address");
+  if contact_name.is_some() || contact_email.is_some() {
+    let mut contact = json!({ "name": contact_name.map(|name| name.as_str()).unwrap_or("unknown") });
+    if let Some(email) = contact_email {
+      contact["details"] = json!({ "emailAddress": email });
+    }
+    payload["contacts"] = json!([contact]);
+  }
+
+  if let Value::Object(attributes) = &mut payload {
+    attributes.retain(|_, value| !value.is_null());
+  }
+  payload
+}
+
+impl TableRow for Environment {
+  fn headers() -> Vec<&'static str> {
+    vec!["UUID", "NAME", "DISPLAY NAME", "PRODUCTION"]
+  }
+
+  fn row(&self) -> Vec<String> {
+    vec![
+      self.uuid.clone(),
+      self.name.clone(),
+      self.display_name.clone().unwrap_or_default(),
+      self.production.to_string()
+    ]
+  }
+}
+
+/// Environment with the contacts and timestamp, displayed when describing an environment
+struct EnvironmentDetails(Environment);
+
+impl TableRow for EnvironmentDetails {
+  fn headers() -> Vec<&'static str> {
+    let mut headers = Environment::headers();
+    headers.extend(["CONTACTS", "CREATED AT"]);
+    headers
+  }
+
+  fn row(&self) -> Vec<String> {
+    let contacts = self.0.contacts.iter()
+      .map(|contact| match contact.details.get("emailAddress").and_then(|email| email.as_str()) {
+        Some(email) => format!("{} <{}>", contact.name, email),
+        None => contact.name.clone()
+      })
+      .collect::<Vec<_>>();
+    let mut row = self.0.row();
+    row.push(contacts.join(", "));
+    row.push(self.0.created_at.clone().unwrap_or_default());
+    row
+  }
+}
+
+/// Prints the message for the table format, the other formats render the environment resource
+fn print_environment(environment: &Value, message: String, args: &ArgMatches) -> Result<(), CliError> {
+  let options = OutputOptions::from_args(args);
+  if options.format == OutputFormat::Table {
+    println!("{}", message);
+    Ok(())
+  } else {
+    print_output(&ResourceTable::single(environment, EnvironmentDetails(from_json(environment)?)), &options)
+  }
+}
+
+/// Fetches the environment with the given UUID
+pub async fn fetch_environment(hal_client: &HALClient, uuid: &str) -> Result<HALClient, PactBrokerError> {
+  hal_client.clone()
+    .navigate("pb:environment", &hashmap!{ "uuid".to_string() => uuid.to_string() })
+    .await
+    .map_err(|err| match err {
+      PactBrokerError::NotFound(_) => PactBrokerError::NotFound(format!("No environment with UUID '{}' was found", uuid)),
+      _ => err
+    })
+}
+
+/// Finds the environment with the given name using the `pb:environments` relation
+pub async fn find_environment_by_name(hal_client: &HALClient, name: &str) -> Result<Environment, PactBrokerError> {
+  let environments = fetch_environments(hal_client).await?;
+  let names = environments.iter().map(|environment| environment.name.clone()).collect::<Vec<_>>();
+  environments.into_iter()
+    .find(|environment| environment.name == name)
+    .ok_or_else(|| PactBrokerError::NotFound(format!("No environment found with name '{}'. Available options: {}",
+      name, names.join(", "))))
+}
+
+/// Fetches all the environments using the `pb:environments` relation
+pub async fn fetch_environments(hal_client: &HALClient) -> Result<Vec<Environment>, PactBrokerError> {
+  let response = hal_client.clone()
+    .navigate("pb:environments", &hashmap!{})
+    .await?
+    .path_info
+    .unwrap_or_default();
+  embedded_items(&response, "environments")
+}
+
+/// Fetches the versions of the pacticipant currently deployed to and released to the environment
+pub async fn currently_deployed_versions(hal_client: &HALClient, environment: &Environment, pacticipant: &str) -> Result<(Vec<DeployedVersion>, Vec<ReleasedVersion>), PactBrokerError> {
+  let query = format!("?pacticipant={}", urlencoding::encode(pacticipant));
+  let deployed = match environment.links.href("pb:currently-deployed-deployed-versions") {
+    Some(url) => embedded_items(&hal_client.clone().fetch(format!("{}{}", url, query).as_str()).await?, "deployedVersions")?,
+    None => vec![]
+  };
+  let released = match environment.links.href("pb:currently-supported-released-versions") {
+    Some(url) => embedded_items(&hal_client.clone().fetch(format!("{}{}", url, query).as_str()).await?, "releasedVersions")?,
+    None => vec![]
+  };
+  Ok((deployed, released))
+}
+
+/// Handles the `pact-broker create-environment` command
+pub async fn create_environment(args: &ArgMatches) -> Result<(), CliError> {
+  let hal_client = broker_client(args)?;
+  let payload = environment_payload(args, None);
+
+  let result = async {
+    let hal_client = hal_client.with_index().await?;
+    let url = hal_client.link_url("pb:environments", &hashmap!{})?;
+    hal_client.post_json(url.as_str(), payload.to_string().as_str()).await
+  }.await;
+
+  match result {
+    Ok(environment) => print_environment(&environment, format!("Created {} environment in the Pact Broker with UUID {}",
+      json_str(&environment, "/name"), json_str(&environment, "/uuid")), args),
+    Err(err) => Err(err.into())
+  }
+}
+
+/// Handles the `pact-broker update-environment` command
+pub async fn update_environment(args: &ArgMatches) -> Result<(), CliError> {
+  let uuid = args.get_one::<String>("uuid").cloned().unwrap_or_default();
+  let hal_client = broker_client(args)?;
+
+  let result = async {
+    let hal_client = hal_client.with_index().await?;
+    let url = hal_client.link_url("pb:environment", &hashmap!{ "uuid".to_string() => uuid.clone() })?;
+    let existing = fetch_environment(&hal_client, &uuid).await?.path_info.unwrap_or_default();
+    let payload = environment_payload(args, Some(&existing));
+    hal_client.put_json(url.as_str(), payload.to_string().as_str()).await
+  }.await;
+
+  match result {
+    Ok(environment) => print_environment(&environment,
+      format!("Updated {} environment in the Pact Broker", json_str(&environment, "/name")), args),
+    Err(err) => Err(err.into())
+  }
+}
+
+/// Handles the `pact-broker describe-environment` command
+pub async fn describe_environment(args: &ArgMatches) -> Result<(), CliError> {
+  let uuid = args.get_one::<String>("uuid").cloned().unwrap_or_default();
+  let hal_client = broker_client(args)?;
+
+  match fetch_environment(&hal_client, &uuid).await {
+    Ok(hal_client) => {
+      let environment = hal_client.path_info.unwrap_or_default();
+      let details = EnvironmentDetails(from_json(&environment)?);
+      print_output(&ResourceTable::single(&environment, details), &OutputOptions::from_args(args))
+    }
+    Err(err) => Err(err.into())
+  }
+}
+
+fn confirm(prompt: &str) -> bool {
+  print!("{} [y/N] ", prompt);
+  let _ = io::stdout().flush();
+  let mut answer = String::new();
+  match io::stdin().lock().read_line(&mut answer) {
+    Ok(_) => matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"),
+    Err(_) => false
+  }
+}
+
+/// Handles the `pact-broker delete-environment` command. Asks for confirmation unless `--yes` is given.
+pub async fn delete_environment(args: &ArgMatches) -> Result<(), CliError> {
+  let uuid = args.get_one::<String>("uuid").cloned().unwrap_or_default();
+  let hal_client = broker_client(args)?;
+
+  let environment = fetch_environment(&hal_client, &uuid).await
+    .map_err(CliError::from)?
+    .path_info
+    .unwrap_or_default();
+  let name = json_str(&environment, "/name").to_string();
+
+  if !args.get_flag("yes") && !confirm(format!("Delete the {} environment ({})?", name, uuid).as_str()) {
+    return Err(CliError::Failed("Environment was not deleted".to_string()));
+  }
+
+  let result = async {
+    let hal_client = hal_client.with_index().await?;
+    let url = hal_client.link_url("pb:environment", &hashmap!{ "uuid".to_string() => uuid.clone() })?;
+    hal_client.delete(url.as_str()).await
+  }.await;
+
+  match result {
+    Ok(_) => print_environment(&environment, format!("Deleted environment {} from the Pact Broker", name), args),
+    Err(err) => Err(err.into())
+  }
+}
+
+/// Handles the `pact-broker list-environments` command
+pub async fn list_environments(args: &ArgMatches) -> Result<(), CliError> {
+  let hal_client = broker_client(args)?;
+
+  let result = async {
+    let hal_client = hal_client.with_index().await?;
+    let url = hal_client.link_url("pb:environments", &hashmap!{})?;
+    hal_client.fetch_pages(url.as_str(), "environments", &pagination(args)).await
+  }.await;
+
+  match result {
+    Ok(environments) => {
+      let document = json!({ "_embedded": { "environments": environments } });
+      let environments = embedded_items::<Environment>(&document, "environments")?;
+      print_output(&ResourceTable::new(&document, environments), &OutputOptions::from_args(args))
+    }
+    Err(err) => Err(err.into())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use crate::cli::subcommand_args;
+
+  use super::*;
+
+  #[test]
+  fn environment_payload_for_a_new_environment() {
+    let args = subcommand_args(&["pact-broker", "create-environment", "-b", "http://localhost",
+      "--name", "prod", "--display-name", "Production",
+      "--production", "--contact-name", "Team A", "--contact-email-address", "a@example.com"]);
+    expect!(environment_payload(&args, None)).to(be_equal_to(json!({
+      "name": "prod",
+      "displayName": "Production",
+      "production": true,
+      "contacts": [{ "name": "Team A", "details": { "emailAddress": "a@example.com" } }]
+    })));
+  }
+
+  #[test]
+  fn environment_payload_keeps_the_existing_attributes() {
+    let args = subcommand_args(&["pact-broker", "update-environment", "-b", "http://localhost", "--uuid", "1234", "--no-production"]);
+    let existing = json!({
+      "uuid": "1234",
+      "name": "prod",
+      "displayName": "Production",
+      "production": true,
+      "_links": {}
+    });
+    expect!(environment_payload(&args, Some(&existing))).to(be_equal_to(json!({
+      "name": "prod",
+      "displayName": "Production",
+      "production": false
+    })));
+  }
+}
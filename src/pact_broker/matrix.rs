@@ -0,0 +1,410 @@
+//! Functions for querying the Pact Broker matrix, used by the `can-i-deploy` and `can-i-merge` commands
+
+use std::time::Duration;
+
+use ansi_term::Colour::*;
+use clap::ArgMatches;
+use itertools::Itertools;
+use serde_json::Value;
+use tabled::{builder::Builder, settings::Style};
+use tokio::time::sleep;
+
+use crate::error::CliError;
+
+use super::{HALClient, PactBrokerError};
+use super::output::{print_output, OutputFormat, OutputOptions, ResourceTable, TableRow};
+use super::resources::{from_json, MatrixRow};
+use super::utils::{broker_client, display_notices, json_str};
+
+impl TableRow for MatrixRow {
+  fn headers() -> Vec<&'static str> {
+    vec!["CONSUMER", "C.VERSION", "PROVIDER", "P.VERSION", "SUCCESS?", "VERIFICATION RESULT"]
+  }
+
+  fn row(&self) -> Vec<String> {
+    let result = self.verification_result.as_ref();
+    vec![
+      self.consumer.name.clone(),
+      self.consumer_version().to_string(),
+      self.provider.name.clone(),
+      self.provider_version().to_string(),
+      result.and_then(|result| result.success).map(|success| success.to_string()).unwrap_or_else(|| "???".to_string()),
+      result.and_then(|result| result.links.href("self")).unwrap_or_default().to_string()
+    ]
+  }
+}
+
+/// Selects a pacticipant version (or versions) to be included in or ignored by a matrix query
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MatrixSelector {
+  /// Name of the pacticipant
+  pub pacticipant: String,
+  /// Version number of the pacticipant
+  pub version: Option<String>,
+  /// Use the latest version of the pacticipant
+  pub latest: bool,
+  /// Tag used with `latest`
+  pub tag: Option<String>,
+  /// Branch of the pacticipant version
+  pub branch: Option<String>,
+  /// Use the latest version of the configured main branch of the pacticipant
+  pub main_branch: bool
+}
+
+impl MatrixSelector {
+  /// Creates a selector for the given pacticipant
+  pub fn new(pacticipant: &str) -> Self {
+    MatrixSelector { pacticipant: pacticipant.to_string(), .. MatrixSelector::default() }
+  }
+
+  fn query_params(&self, prefix: &str) -> Vec<(String, String)> {
+    let mut params = vec![(format!("{}[][pacticipant]", prefix), self.pacticipant.clone())];
+    if let Some(version) = &self.version {
+      params.push((format!("{}[][version]", prefix), version.clone()));
+    }
+    if self.latest {
+      params.push((format!("{}[][latest]", prefix), "true".to_string()));
+    }
+    if let Some(tag) = &self.tag {
+      params.push((format!("{}[][tag]", prefix), tag.clone()));
+    }
+    if let Some(branch) = &self.branch {
+      params.push((format!("{}[][branch]", prefix), branch.clone()));
+    }
+    if self.main_branch {
+      params.push((format!("{}[][mainBranch]", prefix), "true".to_string()));
+    }
+    params
+  }
+}
+
+/// Options for a matrix query
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MatrixQuery {
+  /// Pacticipant versions to check
+  pub selectors: Vec<MatrixSelector>,
+  /// Pacticipant versions to ignore
+  pub ignore: Vec<MatrixSelector>,
+  /// Environment the pacticipants are to be deployed to
+  pub to_environment: Option<String>,
+  /// Tag representing the branch or environment of the integrated applications
+  pub to_tag: Option<String>,
+  /// Check against the latest version of the main branch of each integrated application
+  pub main_branch: bool
+}
+
+impl MatrixQuery {
+  /// Returns the query string for the `/matrix` resource
+  pub fn to_query_string(&self) -> String {
+    let mut params = self.selectors.iter()
+      .flat_map(|selector| selector.query_params("q"))
+      .collect_vec();
+
+    params.push(("latestby".to_string(),
+      if self.selectors.len() == 1 { "cvp" } else { "cvpv" }.to_string()));
+    if let Some(environment) = &self.to_environment {
+      params.push(("environment".to_string(), environment.clone()));
+    }
+    if let Some(tag) = &self.to_tag {
+      params.push(("latest".to_string(), "true".to_string()));
+      params.push(("tag".to_string(), tag.clone()));
+    } else if self.main_branch {
+      params.push(("mainBranch".to_string(), "true".to_string()));
+      params.push(("latest".to_string(), "true".to_string()));
+    } else if self.selectors.len() == 1 && self.to_environment.is_none() {
+      params.push(("latest".to_string(), "true".to_string()));
+    }
+    params.extend(self.ignore.iter().flat_map(|selector| selector.query_params("ignore")));
+
+    url::form_urlencoded::Serializer::new(String::new())
+      .extend_pairs(params)
+      .finish()
+  }
+}
+
+/// Values of a repeatable argument, along with the position they were given on the command line.
+/// Arguments that the command does not define have no values.
+fn indexed_values(args: &ArgMatches, id: &str) -> Vec<(usize, String)> {
+  if args.try_contains_id(id).is_err() {
+    return vec![];
+  }
+  match (args.indices_of(id), args.get_many::<String>(id)) {
+    (Some(indices), Some(values)) => indices.zip(values.cloned()).collect(),
+    _ => vec![]
+  }
+}
+
+/// Builds the selectors from the command arguments. The `--version`, `--latest`, `--branch` and
+/// `--main-branch` arguments apply to the closest `--pacticipant` (or `--ignore`) before them.
+pub fn selectors_from_args(args: &ArgMatches) -> anyhow::Result<(Vec<MatrixSelector>, Vec<MatrixSelector>)> {
+  let mut anchors = indexed_values(args, "pacticipant").into_iter()
+    .map(|(index, name)| (index, false, MatrixSelector::new(&name)))
+    .chain(indexed_values(args, "ignore").into_iter()
+      .map(|(index, name)| (index, true, MatrixSelector::new(&name))))
+    .sorted_by_key(|(index, _, _)| *index)
+    .collect_vec();
+
+  for id in ["version", "latest", "branch", "main-branch"] {
+    for (index, value) in indexed_values(args, id) {
+      let selector = anchors.iter_mut()
+        .rev()
+        .find(|(anchor, _, _)| *anchor < index)
+        .map(|(_, _, selector)| selector)
+        .ok_or_else(|| anyhow::anyhow!("The --{} option must be specified after the --pacticipant that it relates to", id))?;
+      match id {
+        "version" => selector.version = Some(value),
+        "latest" => {
+          selector.latest = true;
+          if !value.is_empty() {
+            selector.tag = Some(value);
+          }
+        },
+        "branch" => selector.branch = Some(value),
+        _ => selector.main_branch = true
+      }
+    }
+  }
+
+  let (ignore, selectors): (Vec<_>, Vec<_>) = anchors.into_iter()
+    .partition(|(_, ignore, _)| *ignore);
+  Ok((
+    selectors.into_iter().map(|(_, _, selector)| selector).collect(),
+    ignore.into_iter().map(|(_, _, selector)| selector).collect()
+  ))
+}
+
+/// Fetches the matrix for the query, retrying while there are unknown verification results
+pub async fn fetch_matrix(
+  hal_client: &HALClient,
+  query: &MatrixQuery,
+  retry_while_unknown: u32,
+  retry_interval: u64
+) -> Result<Value, PactBrokerError> {
+  let path = format!("/matrix?{}", query.to_query_string());
+  let mut attempts = 0;
+  loop {
+    let matrix = hal_client.clone().fetch(path.as_str()).await?;
+    let unknown = matrix.pointer("/summary/unknown").and_then(|unknown| unknown.as_u64()).unwrap_or_default();
+    if unknown == 0 || attempts >= retry_while_unknown {
+      return Ok(matrix);
+    }
+    attempts += 1;
+    eprintln!("Waiting for {} verification result(s) to be published. Retrying in {} seconds ({}/{})",
+      unknown, retry_interval, attempts, retry_while_unknown);
+    sleep(Duration::from_secs(retry_interval)).await;
+  }
+}
+
+/// If the matrix summary indicates that the pacticipant versions are safe to deploy
+pub fn is_deployable(matrix: &Value) -> bool {
+  matrix.pointer("/summary/deployable").and_then(|deployable| deployable.as_bool()).unwrap_or(false)
+}
+
+/// Renders the matrix rows as a table, followed by the verification results, the broker's verdict
+/// and its notices (or reason, if there are no notices)
+pub fn display_matrix(matrix: &Value, verbose: bool) {
+  let rows: Vec<MatrixRow> = matrix.get("matrix").and_then(|rows| from_json(rows).ok()).unwrap_or_default();
+  let mut results = vec![];
+
+  if !rows.is_empty() {
+    let mut builder = Builder::default();
+    builder.push_record(["CONSUMER", "C.VERSION", "PROVIDER", "P.VERSION", "SUCCESS?", "RESULT#"]);
+    for row in &rows {
+      let (success, result_number) = match &row.verification_result {
+        Some(result) => {
+          results.push(result.clone());
+          let success = result.success.map(|success| success.to_string()).unwrap_or_default();
+          (success, results.len().to_string())
+        },
+        None => ("???".to_string(), String::default())
+      };
+      builder.push_record([
+        row.consumer.name.as_str(),
+        row.consumer_version(),
+        row.provider.name.as_str(),
+        row.provider_version(),
+        success.as_str(),
+        result_number.as_str()
+      ]);
+    }
+    let mut table = builder.build();
+    table.with(Style::rounded());
+    println!("{}", table);
+  }
+
+  if !results.is_empty() {
+    println!();
+    println!("VERIFICATION RESULTS");
+    println!("--------------------");
+    for (index, result) in results.iter().enumerate() {
+      let outcome = match result.success {
+        Some(true) => "success",
+        Some(false) => "failure",
+        None => "unknown"
+      };
+      println!("{}. {} ({})", index + 1, result.links.href("self").unwrap_or_default(), outcome);
+    }
+  }
+
+  println!();
+  if is_deployable(matrix) {
+    println!("{}", Green.paint("Computer says yes \\o/"));
+  } else {
+    println!("{}", Red.paint("Computer says no ¯\\_(ツ)_/¯"));
+  }
+  println!();
+
+  let has_notices = matrix.get("notices")
+    .and_then(|notices| notices.as_array())
+    .map(|notices| !notices.is_empty())
+    .unwrap_or(false);
+  if has_notices {
+    display_notices(matrix, verbose);
+  } else {
+    println!("{}", json_str(matrix, "/summary/reason"));
+  }
+}
+
+/// Renders the matrix in the requested output format, returning an error if the pacticipant
+/// versions are not deployable. The table format includes the verification results and the
+/// broker's verdict, the other formats only render the matrix.
+pub fn handle_matrix_result(matrix: &Value, options: &OutputOptions, verbose: bool, dry_run: bool) -> Result<(), CliError> {
+  let table_output = options.format == OutputFormat::Table;
+  if table_output {
+    display_matrix(matrix, verbose);
+  } else {
+    let rows: Vec<MatrixRow> = matrix.get("matrix").map(from_json).transpose()?.unwrap_or_default();
+    print_output(&ResourceTable::new(matrix, rows), options)?;
+  }
+
+  if dry_run {
+    if table_output {
+      println!();
+      println!("{}", Yellow.paint("[dry-run] Dry run enabled - ignoring any failures"));
+    }
+    Ok(())
+  } else if is_deployable(matrix) {
+    Ok(())
+  } else {
+    Err(CliError::NotDeployable)
+  }
+}
+
+/// If dry-run mode has been enabled with the flag or the given environment variable
+pub fn dry_run_enabled(args: &ArgMatches, env_var: &str) -> bool {
+  args.get_flag("dry-run") || std::env::var(env_var)
+    .map(|value| value.to_lowercase() == "true")
+    .unwrap_or(false)
+}
+
+/// Handles the `pact-broker can-i-deploy` command
+pub async fn can_i_deploy(args: &ArgMatches) -> Result<(), CliError> {
+  let (selectors, mut ignore) = selectors_from_args(args).map_err(CliError::from)?;
+  if let Ok(names) = std::env::var("PACT_BROKER_CAN_I_DEPLOY_IGNORE") {
+    ignore.extend(names.split(',')
+      .map(|name| name.trim())
+      .filter(|name| !name.is_empty())
+      .map(MatrixSelector::new));
+  }
+  let query = MatrixQuery {
+    selectors,
+    ignore,
+    to_environment: args.get_one::<String>("to-environment").cloned(),
+    to_tag: args.get_one::<String>("to").cloned(),
+    main_branch: false
+  };
+  check_matrix(args, &query, "PACT_BROKER_CAN_I_DEPLOY_DRY_RUN").await
+}
+
+/// Handles the `pact-broker can-i-merge` command. The pacticipant versions are checked against the
+/// latest version of the main branch of each of the pacticipants they are integrated with.
+pub async fn can_i_merge(args: &ArgMatches) -> Result<(), CliError> {
+  let (selectors, ignore) = selectors_from_args(args).map_err(CliError::from)?;
+  let query = MatrixQuery {
+    selectors,
+    ignore,
+    main_branch: true,
+    .. MatrixQuery::default()
+  };
+  check_matrix(args, &query, "PACT_BROKER_CAN_I_MERGE_DRY_RUN").await
+}
+
+async fn check_matrix(args: &ArgMatches, query: &MatrixQuery, dry_run_env_var: &str) -> Result<(), CliError> {
+  let retry_while_unknown = *args.get_one::<u32>("retry-while-unknown").unwrap_or(&0);
+  let retry_interval = *args.get_one::<u64>("retry-interval").unwrap_or(&10);
+  let options = OutputOptions::from_args(args);
+  let dry_run = dry_run_enabled(args, dry_run_env_var);
+
+  let hal_client = broker_client(args)?;
+  match fetch_matrix(&hal_client, query, retry_while_unknown, retry_interval).await {
+    Ok(matrix) => handle_matrix_result(&matrix, &options, args.get_flag("verbose"), dry_run),
+    Err(err) => {
+      let err = CliError::broker("Failed to query the matrix", err);
+      if dry_run {
+        eprintln!("{}", Red.paint(err.to_string()));
+        Ok(())
+      } else {
+        Err(err)
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+
+  use crate::cli::subcommand_args;
+
+  use super::*;
+
+  #[test]
+  fn selectors_from_args_associates_the_options_with_the_preceding_pacticipant() {
+    let args = subcommand_args(&["pact-broker", "can-i-deploy", "-b", "http://localhost",
+      "--pacticipant", "Foo", "--version", "1", "--pacticipant", "Bar",
+      "--latest", "main", "--ignore", "Baz", "--version", "2", "--pacticipant", "Qux", "--main-branch"]);
+    let (selectors, ignore) = selectors_from_args(&args).unwrap();
+    expect!(selectors).to(be_equal_to(vec![
+      MatrixSelector { version: Some("1".to_string()), .. MatrixSelector::new("Foo") },
+      MatrixSelector { latest: true, tag: Some("main".to_string()), .. MatrixSelector::new("Bar") },
+      MatrixSelector { main_branch: true, .. MatrixSelector::new("Qux") }
+    ]));
+    expect!(ignore).to(be_equal_to(vec![
+      MatrixSelector { version: Some("2".to_string()), .. MatrixSelector::new("Baz") }
+    ]));
+  }
+
+  #[test]
+  fn to_query_string_for_a_single_pacticipant_to_an_environment() {
+    let query = MatrixQuery {
+      selectors: vec![MatrixSelector { version: Some("1.0".to_string()), .. MatrixSelector::new("Foo") }],
+      to_environment: Some("prod".to_string()),
+      .. MatrixQuery::default()
+    };
+    expect!(query.to_query_string()).to(be_equal_to(
+      "q%5B%5D%5Bpacticipant%5D=Foo&q%5B%5D%5Bversion%5D=1.0&latestby=cvp&environment=prod"));
+  }
+
+  #[test]
+  fn to_query_string_for_the_main_branches() {
+    let query = MatrixQuery {
+      selectors: vec![MatrixSelector { version: Some("1.0".to_string()), .. MatrixSelector::new("Foo") }],
+      main_branch: true,
+      .. MatrixQuery::default()
+    };
+    expect!(query.to_query_string()).to(be_equal_to(
+      "q%5B%5D%5Bpacticipant%5D=Foo&q%5B%5D%5Bversion%5D=1.0&latestby=cvp&mainBranch=true&latest=true"));
+  }
+
+  #[test]
+  fn to_query_string_for_a_tag() {
+    let query = MatrixQuery {
+      selectors: vec![MatrixSelector::new("Foo"), MatrixSelector { latest: true, .. MatrixSelector::new("Bar") }],
+      ignore: vec![MatrixSelector::new("Baz")],
+      to_tag: Some("prod".to_string()),
+      .. MatrixQuery::default()
+    };
+    expect!(query.to_query_string()).to(be_equal_to(
+      "q%5B%5D%5Bpacticipant%5D=Foo&q%5B%5D%5Bpacticipant%5D=Bar&q%5B%5D%5Blatest%5D=true&latestby=cvpv&latest=true&tag=prod&ignore%5B%5D%5Bpacticipant%5D=Baz"));
+  }
+}
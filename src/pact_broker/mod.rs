@@ -1,9 +1,25 @@
 //! Structs and functions for interacting with a Pact Broker
 
-use std::collections::HashMap;
+// Lints raised by the baseline code of this module, which is kept as it was
+#![allow(
+  dead_code,
+  clippy::collapsible_match,
+  clippy::len_zero,
+  clippy::match_like_matches_macro,
+  clippy::needless_borrow,
+  clippy::needless_borrows_for_generic_args,
+  clippy::needless_lifetimes,
+  clippy::redundant_closure,
+  clippy::too_many_arguments,
+  clippy::unnecessary_to_owned,
+  clippy::useless_format
+)]
+
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::ops::Not;
 use std::panic::RefUnwindSafe;
 use std::str::from_utf8;
+use std::time::Duration;
 
 use anyhow::anyhow;
 use futures::stream::*;
@@ -13,16 +29,42 @@ use pact_models::http_utils;
 use pact_models::http_utils::HttpAuth;
 use pact_models::json_utils::json_to_string;
 use pact_models::pact::{load_pact_from_json, Pact};
-use regex::{Captures, Regex};
-use reqwest::{Method, Url};
+use reqwest::Url;
+use url::Position;
 use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
 use serde_json::{json, Value};
 use serde_with::skip_serializing_none;
-use tracing::{debug, error, info, trace, warn};
-use pact_matching::Mismatch;
-mod utils;
+use tracing::{debug, error, info, trace};
+pub mod branches;
+pub mod browse;
+pub mod cache;
+pub mod config;
+pub mod deployments;
+pub mod environments;
+pub mod matrix;
+pub mod navigate;
+pub mod output;
+pub mod pacticipants;
+pub mod pacts;
+pub mod provider_contracts;
+pub mod publish_pacts;
+pub mod releases;
+pub mod resources;
+#[cfg(test)]
+mod stub_broker;
+pub mod uri_template;
+pub mod utils;
+pub mod verification_results;
+pub mod versions;
+pub mod webhooks;
+
+pub use reqwest::Method;
 // use crate::{MismatchResult, VERIFIER_VERSION};
 // use pact_matching::metrics::VerificationMetrics;
+use cache::{CachedResponse, ResponseCache};
+use uri_template::TemplateValue;
+pub use utils::RetryPolicy;
 use utils::with_retries;
 
 fn is_true(object: &serde_json::Map<String, Value>, field: &str) -> bool {
@@ -195,7 +237,8 @@ pub struct HALClient {
   url: String,
   path_info: Option<Value>,
   auth: Option<HttpAuth>,
-  retries: u8
+  retry_policy: RetryPolicy,
+  cache: Option<ResponseCache>
 }
 
 impl HALClient {
@@ -204,13 +247,50 @@ impl HALClient {
     HALClient { url: url.to_string(), auth, ..HALClient::default() }
   }
 
+  /// Returns a client that connects to the Pact Broker with the TLS settings
+  pub fn with_tls(self, tls: &TlsOptions) -> reqwest::Result<HALClient> {
+    self.with_connection(tls, None)
+  }
+
+  /// Returns a client that connects to the Pact Broker with the TLS settings, failing requests that
+  /// take longer than the timeout
+  pub fn with_connection(self, tls: &TlsOptions, timeout: Option<Duration>) -> reqwest::Result<HALClient> {
+    let mut builder = client_builder()
+      .danger_accept_invalid_certs(tls.insecure);
+    if let Some(timeout) = timeout {
+      builder = builder.timeout(timeout);
+    }
+    if let Some(pem) = &tls.ca_certificates {
+      for certificate in pem_certificates(pem) {
+        builder = builder.add_root_certificate(reqwest::Certificate::from_pem(certificate.as_bytes())?);
+      }
+    }
+    if let Some(pem) = &tls.client_identity {
+      builder = builder.identity(reqwest::Identity::from_pem(pem)?);
+    }
+    Ok(HALClient { client: builder.build()?, .. self })
+  }
+
+  /// Returns a client that caches the responses from the Pact Broker, and revalidates them with
+  /// conditional requests. If the cache is in offline mode, the cached responses are returned
+  /// without contacting the Pact Broker.
+  pub fn with_cache(self, cache: Option<ResponseCache>) -> HALClient {
+    HALClient { cache, .. self }
+  }
+
+  /// Returns a client that retries failed requests with the policy
+  pub fn with_retry_policy(self, retry_policy: RetryPolicy) -> HALClient {
+    HALClient { retry_policy, .. self }
+  }
+
   fn update_path_info(self, path_info: serde_json::Value) -> HALClient {
     HALClient {
       client: self.client.clone(),
       url: self.url.clone(),
       path_info: Some(path_info),
       auth: self.auth,
-      retries: self.retries
+      retry_policy: self.retry_policy,
+      cache: self.cache
     }
   }
 
@@ -222,12 +302,7 @@ impl HALClient {
   ) -> Result<HALClient, PactBrokerError> {
     trace!("navigate(link='{}', template_values={:?})", link, template_values);
 
-    let client = if self.path_info.is_none() {
-      let path_info = self.clone().fetch("/".into()).await?;
-      self.update_path_info(path_info)
-    } else {
-      self
-    };
+    let client = self.with_index().await?;
 
     let path_info = client.clone().fetch_link(link, template_values).await?;
     let client = client.update_path_info(path_info);
@@ -235,6 +310,151 @@ impl HALClient {
     Ok(client)
   }
 
+  /// Navigates to the resource from the named relation of the current resource, fetching the index
+  /// resource first if no resource has been fetched yet. Unlike `navigate`, the relation name does
+  /// not need to be known at compile time, and templated links are expanded with any RFC 6570
+  /// values. If the relation has several links, one can be selected by name with `relation[name]`.
+  pub async fn navigate_to(
+    self,
+    relation: &str,
+    template_values: &HashMap<String, TemplateValue>
+  ) -> Result<HALClient, PactBrokerError> {
+    trace!("navigate_to(relation='{}', template_values={:?})", relation, template_values);
+
+    let client = self.with_index().await?;
+    let link = client.find_relation_link(relation)?;
+    let link_url = if link.templated {
+      client.expand_link_url(&link, template_values)?
+    } else {
+      link.href.clone().ok_or_else(|| PactBrokerError::LinkError(
+        format!("Link is malformed, there is no href. URL: '{}', LINK: '{}'", client.url, relation)))?
+    };
+    let url = client.url.parse::<Url>()?.join(&link_url)?;
+    let path_info = client.clone().fetch(&url[Position::BeforePath..]).await?;
+    Ok(client.update_path_info(path_info))
+  }
+
+  /// Finds the link for the relation of the current resource. A link from a relation with several
+  /// links is selected by name (or title) with `relation[name]`.
+  fn find_relation_link(&self, relation: &str) -> Result<Link, PactBrokerError> {
+    let (name, selector) = match relation.strip_suffix(']').and_then(|relation| relation.split_once('[')) {
+      Some((name, selector)) => (name, Some(selector)),
+      None => (relation, None)
+    };
+    let links = self.path_info.as_ref()
+      .and_then(|json| json.get("_links"))
+      .and_then(|links| links.as_object())
+      .ok_or_else(|| PactBrokerError::LinkError(format!(
+        "Expected a HAL+JSON response from the pact broker, but got a response with no '_links'. URL: '{}', LINK: '{}'",
+        self.url, relation)))?;
+    let link_data = links.get(name)
+      .ok_or_else(|| PactBrokerError::LinkError(format!(
+        "Link '{}' was not found in the response, only the following links where found: {}. URL: '{}', LINK: '{}'",
+        name, links.keys().join(", "), self.url, relation)))?;
+    let candidates = match link_data {
+      Value::Array(items) => items.iter().filter_map(|item| item.as_object()).collect_vec(),
+      Value::Object(item) => vec![item],
+      _ => vec![]
+    };
+    let link_name = |item: &serde_json::Map<String, Value>| item.get("name")
+      .or_else(|| item.get("title"))
+      .map(as_string)
+      .unwrap_or_default();
+    let selected = match selector {
+      Some(selector) => candidates.iter().find(|item| link_name(item) == selector),
+      None if candidates.len() == 1 => candidates.first(),
+      None => None
+    };
+    match selected {
+      Some(item) => Ok(Link::from_json(name, item)),
+      None => Err(PactBrokerError::LinkError(format!(
+        "Could not select a link for '{}', select one of the links by name with '{}[name]'. Available names: {}. URL: '{}'",
+        relation, name, candidates.iter().map(|item| link_name(item)).join(", "), self.url)))
+    }
+  }
+
+  /// Fetches the index resource of the Pact Broker, if no previous resource has been fetched
+  pub async fn with_index(self) -> Result<HALClient, PactBrokerError> {
+    if self.path_info.is_none() {
+      let path_info = self.clone().fetch("/").await?;
+      Ok(self.update_path_info(path_info))
+    } else {
+      Ok(self)
+    }
+  }
+
+  /// Returns the URL of the named link of the current resource, expanding any template values
+  pub fn link_url(
+    &self,
+    link: &'static str,
+    template_values: &HashMap<String, String>
+  ) -> Result<String, PactBrokerError> {
+    let link_data = self.find_link(link)?;
+    if link_data.templated {
+      self.parse_link_url(&link_data, template_values)
+    } else {
+      link_data.href.ok_or_else(|| PactBrokerError::LinkError(
+        format!("Link is malformed, there is no href. URL: '{}', LINK: '{}'", self.url, link)
+      ))
+    }
+  }
+
+  /// Returns a stream of the embedded items of a paginated collection resource. The pages are
+  /// fetched as the stream is polled, by following the `next` links, so callers that only need the
+  /// first few items (with `take`) do not fetch the whole collection. Items are read from
+  /// `_embedded.<embedded>`, or from the `<embedded>` attribute for resources that do not embed them.
+  /// If a page size is given, it is requested with the `pageSize` query parameter on the first page
+  /// and the Pact Broker carries it over to the `next` links.
+  pub fn paginate(&self, url: &str, embedded: &str, page_size: Option<usize>) -> BoxStream<'static, Result<Value, PactBrokerError>> {
+    let first_page = match page_size {
+      Some(size) => with_query_parameter(url, "pageSize", size.to_string().as_str()),
+      None => url.to_string()
+    };
+    let state = PageState {
+      client: self.clone(),
+      embedded: embedded.to_string(),
+      next: Some(first_page),
+      items: VecDeque::new(),
+      visited: HashSet::new()
+    };
+    unfold(state, |mut state| async move {
+      loop {
+        if let Some(item) = state.items.pop_front() {
+          return Some((Ok(item), state));
+        }
+        let url = state.next.take()?;
+        if !state.visited.insert(url.clone()) {
+          return None;
+        }
+        match state.client.clone().fetch(url.as_str()).await {
+          Ok(page) => {
+            state.items.extend(page_items(&page, state.embedded.as_str()));
+            state.next = page.pointer("/_links/next/href")
+              .and_then(|href| href.as_str())
+              .map(|href| href.to_string());
+          }
+          Err(err) => return Some((Err(err), state))
+        }
+      }
+    }).boxed()
+  }
+
+  /// Fetches every page of a paginated collection resource by following the `next` links, and
+  /// returns the embedded items from all the pages
+  pub async fn fetch_all_pages(&self, url: &str, embedded: &str) -> Result<Vec<Value>, PactBrokerError> {
+    self.paginate(url, embedded, None).try_collect().await
+  }
+
+  /// Fetches the embedded items of a paginated collection resource, stopping once the limit of
+  /// items has been returned. All the pages are fetched if no limit is given.
+  pub async fn fetch_pages(&self, url: &str, embedded: &str, pagination: &Pagination) -> Result<Vec<Value>, PactBrokerError> {
+    let items = self.paginate(url, embedded, pagination.page_size);
+    match pagination.limit {
+      Some(limit) => items.take(limit).try_collect().await,
+      None => items.try_collect().await
+    }
+  }
+
     fn find_link(&self, link: &'static str) -> Result<Link, PactBrokerError> {
         match self.path_info {
             None => Err(PactBrokerError::LinkError(format!("No previous resource has been fetched from the pact broker. URL: '{}', LINK: '{}'",
@@ -286,7 +506,22 @@ impl HALClient {
 
     let base_url = self.url.parse::<Url>()?;
     let joined_url = base_url.join(&link_url)?;
-    self.fetch(joined_url.path().into()).await
+    self.fetch(&joined_url[Position::BeforePath..]).await
+  }
+
+  /// Fetches the resource at the path, deserialising it into one of the typed resource models
+  /// (like [`resources::Pacticipant`])
+  pub async fn fetch_typed<T: DeserializeOwned>(self, path: &str) -> Result<T, PactBrokerError> {
+    let json = self.fetch(path).await?;
+    resources::from_json(&json)
+  }
+
+  /// Returns the current resource (the last one navigated to) as one of the typed resource models
+  pub fn resource<T: DeserializeOwned>(&self) -> Result<T, PactBrokerError> {
+    match &self.path_info {
+      Some(json) => resources::from_json(json),
+      None => Err(PactBrokerError::ContentError("No resource has been fetched from the Pact Broker".to_string()))
+    }
   }
 
   pub async fn fetch(self, path: &str) -> Result<Value, PactBrokerError> {
@@ -302,23 +537,64 @@ impl HALClient {
       broker_url.join(path)?
     };
 
-    let request_builder = match self.auth {
+    let cache_key = ResponseCache::key(url.as_str(), &self.auth);
+    let cached = self.cache.as_ref().and_then(|cache| cache.load(&cache_key));
+    if self.cache.as_ref().is_some_and(|cache| cache.is_offline()) {
+      return match cached {
+        Some(cached) => {
+          debug!("Using the cached response for '{}' in offline mode", url);
+          Ok(cached.body)
+        }
+        None => Err(PactBrokerError::IoError(format!(
+          "There is no cached response for pact broker path '{}' to use in offline mode. URL: '{}'", path, self.url)))
+      };
+    }
+
+    let mut request_builder = match self.auth {
         Some(ref auth) => match auth {
-            HttpAuth::User(username, password) => self.client.get(url).basic_auth(username, password.clone()),
-            HttpAuth::Token(token) => self.client.get(url).bearer_auth(token),
-            _ => self.client.get(url)
+            HttpAuth::User(username, password) => self.client.get(url.clone()).basic_auth(username, password.clone()),
+            HttpAuth::Token(token) => self.client.get(url.clone()).bearer_auth(token),
+            _ => self.client.get(url.clone())
         },
-        None => self.client.get(url)
+        None => self.client.get(url.clone())
     }.header("accept", "application/hal+json, application/json");
+    if let Some(cached) = &cached {
+      if let Some(etag) = &cached.etag {
+        request_builder = request_builder.header(reqwest::header::IF_NONE_MATCH, etag);
+      }
+      if let Some(last_modified) = &cached.last_modified {
+        request_builder = request_builder.header(reqwest::header::IF_MODIFIED_SINCE, last_modified);
+      }
+    }
 
-    let response = utils::with_retries(self.retries, request_builder).await
-      .map_err(|err| {
-          PactBrokerError::IoError(format!("Failed to access pact broker path '{}' - {}. URL: '{}'",
-              &path,
-              err,
-              &self.url,
-          ))
-      })?;
+    let response = match utils::with_retries(&self.retry_policy, request_builder).await {
+      Ok(response) => response,
+      Err(err) => {
+        return Err(PactBrokerError::IoError(format!("Failed to access pact broker path '{}' - {}. URL: '{}'",
+          &path,
+          err,
+          &self.url,
+        )));
+      }
+    };
+
+    if let Some(cache) = &self.cache {
+      if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+        if let Some(cached) = cached {
+          debug!("Response for '{}' has not been modified, using the cached response", url);
+          cache.store(&cache_key, &cached);
+          return Ok(cached.body);
+        }
+      }
+      let header = |name| response.headers().get(name)
+        .and_then(|value: &reqwest::header::HeaderValue| value.to_str().ok())
+        .map(|value| value.to_string());
+      let etag = header(reqwest::header::ETAG);
+      let last_modified = header(reqwest::header::LAST_MODIFIED);
+      let body = self.parse_broker_response(path.to_string(), response).await?;
+      cache.store(&cache_key, &CachedResponse { url: url.to_string(), etag, last_modified, body: body.clone() });
+      return Ok(body);
+    }
 
     self.parse_broker_response(path.to_string(), response)
         .await
@@ -334,7 +610,9 @@ impl HALClient {
       let status_code = response.status();
 
       if status_code.is_success() {
-        if is_json_content_type {
+        if status_code.as_u16() == 204 {
+          Ok(Value::Null)
+        } else if is_json_content_type {
           response.json::<Value>()
             .await
             .map_err(|err| PactBrokerError::ContentError(
@@ -354,7 +632,7 @@ impl HALClient {
                       status_code, self.url
               )
           ))
-      } else if status_code.as_u16() == 400 {
+      } else if matches!(status_code.as_u16(), 400 | 409 | 422) {
         let body = response.bytes()
           .await
           .map_err(|_| PactBrokerError::IoError(
@@ -388,30 +666,34 @@ impl HALClient {
     }
 
     fn parse_link_url(&self, link: &Link, values: &HashMap<String, String>) -> Result<String, PactBrokerError> {
-      match link.href {
-        Some(ref href) => {
-          debug!("templated URL = {}", href);
-          let re = Regex::new(r"\{(\w+)}").unwrap();
-          let final_url = re.replace_all(href, |caps: &Captures| {
-            let lookup = caps.get(1).unwrap().as_str();
-            trace!("Looking up value for key '{}'", lookup);
-            match values.get(lookup) {
-              Some(val) => urlencoding::encode(val.as_str()).to_string(),
-              None => {
-                warn!("No value was found for key '{}', mapped values are {:?}", lookup, values);
-                format!("{{{}}}", lookup)
-              }
-            }
-          });
-          debug!("final URL = {}", final_url);
-          Ok(final_url.to_string())
-        },
-        None => Err(PactBrokerError::LinkError(
-          format!("Expected a HAL+JSON response from the pact broker, but got a link with no HREF. URL: '{}', LINK: '{}'",
-          self.url, link.name)))
-      }
+      let values = values.iter()
+        .map(|(name, value)| (name.clone(), TemplateValue::from(value.as_str())))
+        .collect();
+      self.expand_link_url(link, &values)
     }
 
+  /// Expands the HREF of the link as a URI template (RFC 6570) with the values. Query parameters
+  /// without a value are removed from the URL, and variables in the path without a value are an
+  /// error.
+  pub fn expand_link_url(&self, link: &Link, values: &HashMap<String, TemplateValue>) -> Result<String, PactBrokerError> {
+    match link.href {
+      Some(ref href) => {
+        debug!("templated URL = {}", href);
+        let undefined = uri_template::undefined_variables(href, values);
+        if !undefined.is_empty() {
+          return Err(PactBrokerError::LinkError(format!("No value was given for {} in the templated link '{}'. URL: '{}', LINK: '{}'",
+            undefined.iter().map(|name| format!("'{}'", name)).join(", "), href, self.url, link.name)));
+        }
+        let final_url = uri_template::expand(href, values)?;
+        debug!("final URL = {}", final_url);
+        Ok(final_url)
+      },
+      None => Err(PactBrokerError::LinkError(
+        format!("Expected a HAL+JSON response from the pact broker, but got a link with no HREF. URL: '{}', LINK: '{}'",
+        self.url, link.name)))
+    }
+  }
+
   /// Iterate over all the links by name
   pub fn iter_links(&self, link: &str) -> Result<Vec<Link>, PactBrokerError> {
     match self.path_info {
@@ -436,28 +718,130 @@ impl HALClient {
     }
   }
 
-  async fn post_json(&self, url: &str, body: &str) -> Result<serde_json::Value, PactBrokerError> {
+  /// Posts the JSON body to the URL, returning the resource from the response. If the response
+  /// has no body but has a `Location` header (like a `201 Created`), the created resource is
+  /// fetched from the location. A response with no body and no location returns `Value::Null`.
+  pub async fn post_json(&self, url: &str, body: &str) -> Result<serde_json::Value, PactBrokerError> {
     trace!("post_json(url='{}', body='{}')", url, body);
 
     self.send_document(url, body, Method::POST).await
   }
 
-  async fn put_json(&self, url: &str, body: &str) -> Result<serde_json::Value, PactBrokerError> {
+  /// Puts the JSON body to the URL, returning the resource from the response (see [`HALClient::post_json`])
+  pub async fn put_json(&self, url: &str, body: &str) -> Result<serde_json::Value, PactBrokerError> {
     trace!("put_json(url='{}', body='{}')", url, body);
 
     self.send_document(url, body, Method::PUT).await
   }
 
-  async fn send_document(&self, url: &str, body: &str, method: Method) -> Result<Value, PactBrokerError> {
-    debug!("Sending JSON to {} using {}: {}", url, method, body);
+  /// Patches the resource at the URL with the JSON body, returning the resource from the response
+  /// (see [`HALClient::post_json`])
+  pub async fn patch_json(&self, url: &str, body: &str) -> Result<serde_json::Value, PactBrokerError> {
+    trace!("patch_json(url='{}', body='{}')", url, body);
 
-    let base_url = &self.url.parse::<Url>()?;
-    let url = if url.starts_with("/") {
-      base_url.join(url)?
+    self.send_document(url, body, Method::PATCH).await
+  }
+
+  /// Deletes the resource at the URL. Returns `Value::Null` for a `204 No Content` response.
+  pub async fn delete(&self, url: &str) -> Result<serde_json::Value, PactBrokerError> {
+    trace!("delete(url='{}')", url);
+
+    self.send_document(url, "", Method::DELETE).await
+  }
+
+  /// Serialises the body as JSON and sends it to the URL of the named link of the current
+  /// resource, using the given HTTP method. The index resource is fetched first if no resource has
+  /// been fetched yet.
+  pub async fn send_to_link<B: Serialize>(
+    &self,
+    method: Method,
+    link: &'static str,
+    template_values: &HashMap<String, String>,
+    body: &B
+  ) -> Result<Value, PactBrokerError> {
+    let client = self.clone().with_index().await?;
+    let url = client.link_url(link, template_values)?;
+    let body = serde_json::to_string(body)
+      .map_err(|err| PactBrokerError::ContentError(format!("Could not serialise the request body as JSON - {}", err)))?;
+    client.send_document(url.as_str(), body.as_str(), method).await
+  }
+
+  /// Posts the body as JSON to the URL of the named link of the current resource
+  pub async fn post_link<B: Serialize>(
+    &self,
+    link: &'static str,
+    template_values: &HashMap<String, String>,
+    body: &B
+  ) -> Result<Value, PactBrokerError> {
+    self.send_to_link(Method::POST, link, template_values, body).await
+  }
+
+  /// Puts the body as JSON to the URL of the named link of the current resource
+  pub async fn put_link<B: Serialize>(
+    &self,
+    link: &'static str,
+    template_values: &HashMap<String, String>,
+    body: &B
+  ) -> Result<Value, PactBrokerError> {
+    self.send_to_link(Method::PUT, link, template_values, body).await
+  }
+
+  /// Patches the resource at the URL of the named link of the current resource with the body as JSON
+  pub async fn patch_link<B: Serialize>(
+    &self,
+    link: &'static str,
+    template_values: &HashMap<String, String>,
+    body: &B
+  ) -> Result<Value, PactBrokerError> {
+    self.send_to_link(Method::PATCH, link, template_values, body).await
+  }
+
+  /// Deletes the resource at the URL of the named link of the current resource
+  pub async fn delete_link(
+    &self,
+    link: &'static str,
+    template_values: &HashMap<String, String>
+  ) -> Result<Value, PactBrokerError> {
+    let client = self.clone().with_index().await?;
+    let url = client.link_url(link, template_values)?;
+    client.delete(url.as_str()).await
+  }
+
+  /// Posts the JSON to the URL, returning the status code and JSON body of the response even if
+  /// the request was not successful. This is for resources that describe failures in the response
+  /// body, like the result of executing a webhook. The request is not retried on server errors, as
+  /// those are the failures being reported.
+  pub async fn post_json_with_status(&self, url: &str, body: &str) -> Result<(u16, Value), PactBrokerError> {
+    trace!("post_json_with_status(url='{}', body='{}')", url, body);
+
+    let (url, request_builder) = self.document_request(url, body, Method::POST)?;
+    let response = request_builder.send().await
+      .map_err(|err| PactBrokerError::IoError(
+        format!("Failed to send JSON to the pact broker URL '{}' - IoError {}", url, err)
+      ))?;
+    let status = response.status().as_u16();
+    if json_content_type(&response) {
+      let body = response.json::<Value>()
+        .await
+        .map_err(|err| PactBrokerError::ContentError(
+          format!("Did not get a valid JSON response body from pact broker path '{}' - {}. URL: '{}'",
+            url.path(), err, self.url)
+        ))?;
+      Ok((status, body))
     } else {
-      let url = url.parse::<Url>()?;
-      base_url.join(&url.path())?
-    };
+      self.parse_broker_response(url.path().to_string(), response).await
+        .map(|body| (status, body))
+    }
+  }
+
+  fn document_request(&self, url: &str, body: &str, method: Method) -> Result<(Url, reqwest::RequestBuilder), PactBrokerError> {
+    if self.cache.as_ref().is_some_and(|cache| cache.is_offline()) {
+      return Err(PactBrokerError::IoError(format!(
+        "Can not send a {} request to the pact broker URL '{}' in offline mode", method, url)));
+    }
+    debug!("Sending JSON to {} using {}: {}", url, method, body);
+
+    let url = document_url(&self.url, url)?;
 
     let request_builder = match self.auth {
       Some(ref auth) => match auth {
@@ -476,15 +860,40 @@ impl HALClient {
       .header("Accept", "application/json")
       .body(body.to_string());
 
-    let response = with_retries(self.retries, request_builder).await;
+    Ok((url, request_builder))
+  }
+
+  async fn send_document(&self, url: &str, body: &str, method: Method) -> Result<Value, PactBrokerError> {
+    let (url, request_builder) = self.document_request(url, body, method)?;
+    let response = with_retries(&self.retry_policy, request_builder).await;
     match response {
-      Ok(res) => self.parse_broker_response(url.path().to_string(), res).await,
+      Ok(res) => self.parse_document_response(&url, res).await,
       Err(err) => Err(PactBrokerError::IoError(
         format!("Failed to send JSON to the pact broker URL '{}' - IoError {}", url, err)
       ))
     }
   }
 
+  /// Parses the response to a write request. Successful responses without a JSON body (like
+  /// `201 Created` or `204 No Content`) follow the `Location` header if there is one.
+  async fn parse_document_response(&self, url: &Url, response: reqwest::Response) -> Result<Value, PactBrokerError> {
+    if response.status().is_success() && !json_content_type(&response) {
+      let location = response.headers().get(reqwest::header::LOCATION)
+        .and_then(|location| location.to_str().ok())
+        .map(|location| location.to_string());
+      match location {
+        Some(location) => {
+          debug!("Following the location '{}' of the response from {}", location, url);
+          let location = url.join(location.as_str())?;
+          self.clone().fetch(&location[Position::BeforePath..]).await
+        }
+        None => Ok(Value::Null)
+      }
+    } else {
+      self.parse_broker_response(url.path().to_string(), response).await
+    }
+  }
+
   fn with_doc_context(self, doc_attributes: &[Link]) -> Result<HALClient, PactBrokerError> {
     let links: serde_json::Map<String, serde_json::Value> = doc_attributes.iter()
       .map(|link| (link.name.clone(), link.as_json())).collect();
@@ -495,6 +904,51 @@ impl HALClient {
   }
 }
 
+/// Resolves the URL to send a document to against the base URL of the Pact Broker. Absolute URLs
+/// are resolved by their path and query, so that documents are always sent to the configured broker.
+fn document_url(base_url: &str, url: &str) -> Result<Url, PactBrokerError> {
+  let base_url = base_url.parse::<Url>()?;
+  if url.starts_with('/') {
+    Ok(base_url.join(url)?)
+  } else {
+    let url = url.parse::<Url>()?;
+    Ok(base_url.join(&url[Position::BeforePath..])?)
+  }
+}
+
+/// Options for fetching a paginated collection resource
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Pagination {
+  /// Number of items to request per page. The Pact Broker default is used if not set.
+  pub page_size: Option<usize>,
+  /// Maximum number of items to return. All the items are returned if not set.
+  pub limit: Option<usize>
+}
+
+/// State of the stream returned by `HALClient::paginate`
+struct PageState {
+  client: HALClient,
+  embedded: String,
+  next: Option<String>,
+  items: VecDeque<Value>,
+  visited: HashSet<String>
+}
+
+/// Returns the items in a page of a collection resource, either embedded or as an attribute
+fn page_items(page: &Value, embedded: &str) -> Vec<Value> {
+  page.pointer(format!("/_embedded/{}", embedded).as_str())
+    .or_else(|| page.get(embedded))
+    .and_then(|items| items.as_array())
+    .cloned()
+    .unwrap_or_default()
+}
+
+/// Adds the query parameter to the URL, keeping any existing query parameters
+fn with_query_parameter(url: &str, name: &str, value: &str) -> String {
+  let separator = if url.contains('?') { '&' } else { '?' };
+  format!("{}{}{}={}", url, separator, name, urlencoding::encode(value))
+}
+
 fn handle_validation_errors(body: Value) -> PactBrokerError {
   match &body {
     Value::Object(attrs) => if let Some(errors) = attrs.get("errors") {
@@ -521,17 +975,44 @@ fn handle_validation_errors(body: Value) -> PactBrokerError {
   }
 }
 
+/// TLS settings for connecting to the Pact Broker. The HTTP(S) proxy is taken from the
+/// `HTTP_PROXY`, `HTTPS_PROXY` and `NO_PROXY` environment variables.
+#[derive(Debug, Clone, Default)]
+pub struct TlsOptions {
+  /// PEM encoded CA certificates to trust, in addition to the system certificates
+  pub ca_certificates: Option<Vec<u8>>,
+  /// PEM encoded client certificate and private key to present for mutual TLS
+  pub client_identity: Option<Vec<u8>>,
+  /// Skip the verification of the Pact Broker certificate
+  pub insecure: bool
+}
+
+fn client_builder() -> reqwest::ClientBuilder {
+  reqwest::ClientBuilder::new()
+    .user_agent(format!("{}/{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")))
+}
+
+/// Splits a PEM bundle into the individual certificates
+fn pem_certificates(pem: &[u8]) -> Vec<String> {
+  const END: &str = "-----END CERTIFICATE-----";
+  String::from_utf8_lossy(pem)
+    .split_inclusive(END)
+    .filter(|block| block.contains(END))
+    .map(|block| block.trim().to_string())
+    .collect()
+}
+
 impl Default for HALClient {
   fn default() -> Self {
     HALClient {
-      client: reqwest::ClientBuilder::new()
-        .user_agent(format!("{}/{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")))
+      client: client_builder()
         .build()
         .unwrap(),
       url: "".to_string(),
       path_info: None,
       auth: None,
-      retries: 3
+      retry_policy: RetryPolicy::default(),
+      cache: None
     }
   }
 }
@@ -763,59 +1244,133 @@ pub async fn fetch_pact_from_url(url: &str, auth: &Option<HttpAuth>) -> anyhow::
   Ok((pact, links))
 }
 
-// /// Struct that wraps the result of a verification test
-// pub enum TestResult {
-//   /// Test was OK
-//   Ok(Vec<Option<String>>),
-//   /// Test failed verification. Each value is a tuple of interaction ID, interaction description,
-//   /// mismatches
-//   Failed(Vec<(Option<String>, Option<String>, Option<MismatchResult>)>)
-// }
+/// Failure of an interaction when verifying a pact
+#[derive(Debug, Clone, PartialEq)]
+pub enum InteractionFailure {
+  /// The actual response or message did not match the interaction. The mismatches are in the JSON
+  /// form used by the verifier (with `attribute` and `description` values).
+  Mismatches(Vec<Value>),
+  /// There was an error verifying the interaction
+  Error(String)
+}
 
-// impl TestResult {
-//   /// Convert this test result to a boolean value
-//   pub fn to_bool(&self) -> bool {
-//     match self {
-//       TestResult::Ok(_) => true,
-//       _ => false
-//     }
-//   }
-// }
+/// Struct that wraps the result of a verification test
+#[derive(Debug, Clone, PartialEq)]
+pub enum TestResult {
+  /// Test was OK
+  Ok(Vec<Option<String>>),
+  /// Test failed verification. Each value is a tuple of interaction ID, interaction description,
+  /// failure
+  Failed(Vec<(Option<String>, Option<String>, Option<InteractionFailure>)>)
+}
 
-// /// Publishes the result to the "pb:publish-verification-results" link in the links associated with the pact
-// pub async fn publish_verification_results(
-//   links: Vec<Link>,
-//   broker_url: &str,
-//   auth: Option<HttpAuth>,
-//   result: TestResult,
-//   version: String,
-//   build_url: Option<String>,
-//   provider_tags: Vec<String>,
-//   branch: Option<String>,
-//   metrics_data: Option<&VerificationMetrics>
-// ) -> Result<serde_json::Value, PactBrokerError> {
-//   let hal_client = HALClient::with_url(broker_url, auth.clone());
-
-//   if branch.is_some() {
-//     publish_provider_branch(&hal_client, &links, &branch.unwrap(), &version).await?;
-//   }
+impl TestResult {
+  /// Convert this test result to a boolean value
+  pub fn to_bool(&self) -> bool {
+    matches!(self, TestResult::Ok(_))
+  }
+}
 
-//   if !provider_tags.is_empty() {
-//     publish_provider_tags(&hal_client, &links, provider_tags, &version).await?;
-//   }
+/// Provider version that the verification results are published for
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProviderVersion {
+  /// Provider application version
+  pub version: String,
+  /// URL of the build that verified the pact
+  pub build_url: Option<String>,
+  /// Tags to add to the provider version
+  pub tags: Vec<String>,
+  /// Repository branch of the provider version
+  pub branch: Option<String>
+}
 
-//   let publish_link = links
-//       .iter()
-//       .cloned()
-//       .find(|item| item.name.to_ascii_lowercase() == "pb:publish-verification-results")
-//       .ok_or_else(|| PactBrokerError::LinkError(
-//           "Response from the pact broker has no 'pb:publish-verification-results' link".into()
-//       ))?;
+/// Builds the verification results document to publish to the Pact Broker
+pub fn build_payload(result: &TestResult, provider: &ProviderVersion) -> Value {
+  let mut json = json!({
+    "providerApplicationVersion": provider.version,
+    "success": result.to_bool(),
+    "verifiedBy": {
+      "implementation": env!("CARGO_PKG_NAME"),
+      "version": env!("CARGO_PKG_VERSION")
+    }
+  });
 
-//   let json = build_payload(result, version, build_url, metrics_data);
-//   hal_client.post_json(publish_link.href.unwrap_or_default().as_str(), json.to_string().as_str()).await
-// }
+  if let Some(build_url) = &provider.build_url {
+    json["buildUrl"] = json!(build_url);
+  }
+
+  json["testResults"] = match result {
+    TestResult::Ok(ids) => ids.iter()
+      .flatten()
+      .map(|id| json!({ "interactionId": id, "success": true }))
+      .collect(),
+    TestResult::Failed(results) => results.iter()
+      .map(|(id, description, failure)| {
+        let mut attributes = serde_json::Map::new();
+        if let Some(id) = id {
+          attributes.insert("interactionId".to_string(), json!(id));
+        }
+        if let Some(description) = description {
+          attributes.insert("interactionDescription".to_string(), json!(description));
+        }
+        attributes.insert("success".to_string(), json!(failure.is_none()));
+        match failure {
+          Some(InteractionFailure::Mismatches(mismatches)) => {
+            attributes.insert("mismatches".to_string(), json!(mismatches));
+          }
+          Some(InteractionFailure::Error(message)) => {
+            attributes.insert("exceptions".to_string(), json!([{ "message": message }]));
+          }
+          None => {}
+        }
+        Value::Object(attributes)
+      })
+      .collect()
+  };
+
+  json
+}
 
+/// Publishes the result to the "pb:publish-verification-results" link in the links associated with the pact
+pub async fn publish_verification_results(
+  links: Vec<Link>,
+  broker_url: &str,
+  auth: Option<HttpAuth>,
+  result: TestResult,
+  provider: &ProviderVersion
+) -> Result<Value, PactBrokerError> {
+  let hal_client = HALClient::with_url(broker_url, auth);
+  publish_verification_results_with_client(&hal_client, &links, &result, provider).await
+}
+
+/// Publishes the result to the "pb:publish-verification-results" link in the links associated with
+/// the pact, using the client to connect to the Pact Broker. The branch and tags of the provider
+/// version are published first.
+pub async fn publish_verification_results_with_client(
+  hal_client: &HALClient,
+  links: &[Link],
+  result: &TestResult,
+  provider: &ProviderVersion
+) -> Result<Value, PactBrokerError> {
+  let publish_link = links
+    .iter()
+    .find(|item| item.name.eq_ignore_ascii_case("pb:publish-verification-results"))
+    .cloned()
+    .ok_or_else(|| PactBrokerError::LinkError(
+      "Response from the pact broker has no 'pb:publish-verification-results' link".into()
+    ))?;
+
+  if let Some(branch) = &provider.branch {
+    publish_provider_branch(hal_client, links, branch, &provider.version).await?;
+  }
+
+  if !provider.tags.is_empty() {
+    publish_provider_tags(hal_client, links, provider.tags.clone(), &provider.version).await?;
+  }
+
+  let json = build_payload(result, provider);
+  hal_client.post_json(publish_link.href.unwrap_or_default().as_str(), json.to_string().as_str()).await
+}
 
 async fn publish_provider_tags(
   hal_client: &HALClient,
@@ -2312,3 +2867,239 @@ pub struct PactVerificationProperties {
 //   //   expect!(result).to(be_ok());
 //   // }
 // }
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+
+  use super::*;
+  use super::stub_broker::{Route, StubBroker};
+
+  fn index() -> Route {
+    Route::get("/", json!({
+      "_links": {
+        "pb:pacticipants": { "href": "/pacticipants" },
+        "pb:pacticipant": { "href": "/pacticipants/{pacticipant}", "templated": true }
+      }
+    }))
+  }
+
+  fn page(items: &[&str], next: Option<&str>) -> Value {
+    let mut page = json!({
+      "_embedded": { "pacticipants": items.iter().map(|name| json!({ "name": name })).collect::<Vec<_>>() },
+      "_links": {}
+    });
+    if let Some(next) = next {
+      page["_links"]["next"] = json!({ "href": next });
+    }
+    page
+  }
+
+  fn names(items: &[Value]) -> Vec<&str> {
+    items.iter().map(|item| item["name"].as_str().unwrap_or_default()).collect()
+  }
+
+  #[tokio::test]
+  async fn fetch_all_pages_follows_the_next_links() {
+    let broker = StubBroker::start(vec![
+      Route::get("/pacticipants", page(&["A", "B"], Some("/pacticipants?page=2"))),
+      Route::get("/pacticipants?page=2", page(&["C", "D"], Some("/pacticipants?page=3"))),
+      Route::get("/pacticipants?page=3", page(&["E"], None))
+    ]);
+    let client = HALClient::with_url(broker.url.as_str(), None);
+
+    let items = client.fetch_all_pages("/pacticipants", "pacticipants").await.unwrap();
+
+    expect!(names(&items)).to(be_equal_to(vec!["A", "B", "C", "D", "E"]));
+    expect!(broker.fetched_paths()).to(be_equal_to(vec![
+      "/pacticipants".to_string(), "/pacticipants?page=2".to_string(), "/pacticipants?page=3".to_string()
+    ]));
+  }
+
+  #[tokio::test]
+  async fn paginate_stops_when_a_next_link_points_to_a_page_already_fetched() {
+    let broker = StubBroker::start(vec![
+      Route::get("/pacticipants", page(&["A"], Some("/pacticipants?page=2"))),
+      Route::get("/pacticipants?page=2", page(&["B"], Some("/pacticipants")))
+    ]);
+    let client = HALClient::with_url(broker.url.as_str(), None);
+
+    let items = client.fetch_all_pages("/pacticipants", "pacticipants").await.unwrap();
+
+    expect!(names(&items)).to(be_equal_to(vec!["A", "B"]));
+    expect!(broker.fetched_paths()).to(be_equal_to(vec![
+      "/pacticipants".to_string(), "/pacticipants?page=2".to_string()
+    ]));
+  }
+
+  #[tokio::test]
+  async fn fetch_pages_stops_fetching_once_the_limit_is_reached() {
+    let broker = StubBroker::start(vec![
+      Route::get("/pacticipants?pageSize=2", page(&["A", "B"], Some("/pacticipants?pageSize=2&page=2"))),
+      Route::get("/pacticipants?pageSize=2&page=2", page(&["C", "D"], Some("/pacticipants?pageSize=2&page=3"))),
+      Route::get("/pacticipants?pageSize=2&page=3", page(&["E"], None))
+    ]);
+    let client = HALClient::with_url(broker.url.as_str(), None);
+
+    let items = client.fetch_pages("/pacticipants", "pacticipants", &Pagination { page_size: Some(2), limit: Some(3) }).await.unwrap();
+
+    expect!(names(&items)).to(be_equal_to(vec!["A", "B", "C"]));
+    expect!(broker.fetched_paths()).to(be_equal_to(vec![
+      "/pacticipants?pageSize=2".to_string(), "/pacticipants?pageSize=2&page=2".to_string()
+    ]));
+  }
+
+  #[tokio::test]
+  async fn paginate_returns_the_items_before_an_error_fetching_a_page() {
+    let broker = StubBroker::start(vec![
+      Route::get("/pacticipants", page(&["A", "B"], Some("/pacticipants?page=2")))
+    ]);
+    let client = HALClient::with_url(broker.url.as_str(), None);
+
+    let results = client.paginate("/pacticipants", "pacticipants", None).collect::<Vec<_>>().await;
+
+    expect!(results.len()).to(be_equal_to(3));
+    expect!(results[0].as_ref().ok()).to(be_some().value(&json!({ "name": "A" })));
+    expect!(results[1].as_ref().ok()).to(be_some().value(&json!({ "name": "B" })));
+    expect!(matches!(results[2], Err(PactBrokerError::NotFound(_)))).to(be_true());
+    expect!(client.fetch_all_pages("/pacticipants", "pacticipants").await.is_err()).to(be_true());
+  }
+
+  #[tokio::test]
+  async fn post_link_fetches_the_created_resource_from_the_location_header() {
+    let broker = StubBroker::start(vec![
+      index(),
+      Route::new("POST", "/pacticipants", 201).with_header("Location", "/pacticipants/Foo"),
+      Route::get("/pacticipants/Foo", json!({ "name": "Foo" }))
+    ]);
+    let client = HALClient::with_url(broker.url.as_str(), None);
+
+    let result = client.post_link("pb:pacticipants", &hashmap!{}, &json!({ "name": "Foo" })).await;
+
+    expect!(result.map_err(|err| err.to_string())).to(be_ok().value(json!({ "name": "Foo" })));
+    expect!(broker.requests().iter().map(|request| format!("{} {} {}", request.method, request.path, request.body)).collect::<Vec<_>>())
+      .to(be_equal_to(vec![
+        "GET / ".to_string(),
+        "POST /pacticipants {\"name\":\"Foo\"}".to_string(),
+        "GET /pacticipants/Foo ".to_string()
+      ]));
+  }
+
+  #[tokio::test]
+  async fn navigate_to_fails_if_a_path_variable_has_no_value() {
+    let broker = StubBroker::start(vec![index()]);
+    let client = HALClient::with_url(broker.url.as_str(), None);
+
+    let result = client.navigate_to("pb:pacticipant", &hashmap!{}).await;
+
+    expect!(result.map(|_| ()).map_err(|err| err.to_string())).to(be_err().value(format!(
+      "Error with a HAL link - No value was given for 'pacticipant' in the templated link '/pacticipants/{{pacticipant}}'. URL: '{}', LINK: 'pb:pacticipant'",
+      broker.url)));
+    expect!(broker.fetched_paths()).to(be_equal_to(vec!["/".to_string()]));
+  }
+
+  #[tokio::test]
+  async fn delete_link_returns_null_for_a_no_content_response() {
+    let broker = StubBroker::start(vec![index(), Route::new("DELETE", "/pacticipants/Foo", 204)]);
+    let client = HALClient::with_url(broker.url.as_str(), None);
+
+    let result = client.delete_link("pb:pacticipant", &hashmap!{ "pacticipant".to_string() => "Foo".to_string() }).await;
+
+    expect!(result.map_err(|err| err.to_string())).to(be_ok().value(Value::Null));
+    expect!(broker.requests().last().map(|request| request.method.clone())).to(be_some().value("DELETE".to_string()));
+  }
+
+  #[tokio::test]
+  async fn send_to_link_returns_the_validation_errors_for_an_unprocessable_entity_response() {
+    let broker = StubBroker::start(vec![
+      index(),
+      Route::new("POST", "/pacticipants", 422).with_body(json!({ "errors": { "name": ["can't be blank"] } }))
+    ]);
+    let client = HALClient::with_url(broker.url.as_str(), None);
+
+    let result = client.send_to_link(Method::POST, "pb:pacticipants", &hashmap!{}, &json!({})).await;
+
+    expect!(result.map_err(|err| err.to_string())).to(be_err().value(
+      "failed validation - [\"name: can't be blank\"]".to_string()));
+  }
+
+  #[test]
+  fn document_url_resolves_urls_against_the_broker_url() {
+    expect!(document_url("http://localhost:9292", "/pacticipants/Foo").map(|url| url.to_string()))
+      .to(be_ok().value("http://localhost:9292/pacticipants/Foo".to_string()));
+    expect!(document_url("http://localhost:9292", "http://broker.internal/deployed-versions/1?pacticipant=Foo").map(|url| url.to_string()))
+      .to(be_ok().value("http://localhost:9292/deployed-versions/1?pacticipant=Foo".to_string()));
+  }
+
+  #[test]
+  fn handle_validation_errors_formats_the_field_errors() {
+    let error = handle_validation_errors(json!({ "errors": { "name": ["can't be blank"] } }));
+    expect!(error).to(be_equal_to("name: can't be blank"));
+  }
+
+  #[test]
+  fn page_items_reads_embedded_items_or_the_attribute() {
+    expect!(page_items(&json!({ "_embedded": { "pacticipants": [{ "name": "Foo" }] } }), "pacticipants"))
+      .to(be_equal_to(vec![json!({ "name": "Foo" })]));
+    expect!(page_items(&json!({ "pacts": [{ "createdAt": "today" }] }), "pacts"))
+      .to(be_equal_to(vec![json!({ "createdAt": "today" })]));
+    expect!(page_items(&json!({ "_links": {} }), "pacts").is_empty()).to(be_true());
+  }
+
+  #[test]
+  fn with_query_parameter_keeps_the_existing_query() {
+    expect!(with_query_parameter("http://localhost/pacticipants", "pageSize", "100"))
+      .to(be_equal_to("http://localhost/pacticipants?pageSize=100"));
+    expect!(with_query_parameter("http://localhost/environments?name=test", "pageSize", "100"))
+      .to(be_equal_to("http://localhost/environments?name=test&pageSize=100"));
+  }
+
+  #[test]
+  fn pem_certificates_splits_the_bundle() {
+    let bundle = "# CA 1\n-----BEGIN CERTIFICATE-----\nAAAA\n-----END CERTIFICATE-----\n\n-----BEGIN CERTIFICATE-----\nBBBB\n-----END CERTIFICATE-----\n";
+    expect!(pem_certificates(bundle.as_bytes())).to(be_equal_to(vec![
+      "# CA 1\n-----BEGIN CERTIFICATE-----\nAAAA\n-----END CERTIFICATE-----".to_string(),
+      "-----BEGIN CERTIFICATE-----\nBBBB\n-----END CERTIFICATE-----".to_string()
+    ]));
+  }
+
+  #[test]
+  fn build_payload_with_success() {
+    let provider = ProviderVersion {
+      version: "1".to_string(),
+      build_url: Some("http://build-url".to_string()),
+      .. ProviderVersion::default()
+    };
+    expect!(build_payload(&TestResult::Ok(vec![Some("1".to_string()), None]), &provider)).to(be_equal_to(json!({
+      "providerApplicationVersion": "1",
+      "success": true,
+      "buildUrl": "http://build-url",
+      "testResults": [{ "interactionId": "1", "success": true }],
+      "verifiedBy": { "implementation": env!("CARGO_PKG_NAME"), "version": env!("CARGO_PKG_VERSION") }
+    })));
+  }
+
+  #[test]
+  fn build_payload_with_mismatches_and_errors() {
+    let result = TestResult::Failed(vec![
+      (Some("1234abc".to_string()), Some("int_desc".to_string()), Some(InteractionFailure::Mismatches(vec![
+        json!({ "attribute": "method", "description": "Expected method of PUT but received POST" })
+      ]))),
+      (None, Some("int_desc2".to_string()), Some(InteractionFailure::Error("Bang".to_string())))
+    ]);
+    let provider = ProviderVersion { version: "1".to_string(), .. ProviderVersion::default() };
+    expect!(build_payload(&result, &provider)["testResults"].clone()).to(be_equal_to(json!([
+      {
+        "interactionId": "1234abc",
+        "interactionDescription": "int_desc",
+        "mismatches": [{ "attribute": "method", "description": "Expected method of PUT but received POST" }],
+        "success": false
+      },
+      {
+        "interactionDescription": "int_desc2",
+        "exceptions": [{ "message": "Bang" }],
+        "success": false
+      }
+    ])));
+  }
+}
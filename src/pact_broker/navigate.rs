@@ -0,0 +1,142 @@
+//! Function for exploring the Pact Broker by following a chain of HAL relations from the index
+//! resource, for resources that do not have a dedicated command
+
+use std::collections::HashMap;
+
+use clap::ArgMatches;
+use serde_json::{json, Value};
+
+use crate::error::CliError;
+
+use super::PactBrokerError;
+use super::uri_template::TemplateValue;
+use super::utils::broker_client;
+
+/// Returns the template values from the `--param name=value` arguments. A name given more than once
+/// is expanded as a list.
+pub fn template_params(params: &[String]) -> Result<HashMap<String, TemplateValue>, CliError> {
+  let mut values: HashMap<String, TemplateValue> = HashMap::new();
+  for param in params {
+    let (name, value) = param.split_once('=')
+      .filter(|(name, _)| !name.is_empty())
+      .ok_or_else(|| CliError::InvalidArguments(format!("Invalid parameter '{}', expected NAME=VALUE", param)))?;
+    let value = match values.remove(name) {
+      Some(TemplateValue::String(existing)) => TemplateValue::List(vec![existing, value.to_string()]),
+      Some(TemplateValue::List(mut existing)) => {
+        existing.push(value.to_string());
+        TemplateValue::List(existing)
+      }
+      _ => TemplateValue::String(value.to_string())
+    };
+    values.insert(name.to_string(), value);
+  }
+  Ok(values)
+}
+
+fn tree_value(value: &Value) -> Option<String> {
+  match value {
+    Value::String(s) => Some(s.clone()),
+    Value::Object(map) if map.is_empty() => Some("{}".to_string()),
+    Value::Array(items) if items.is_empty() => Some("[]".to_string()),
+    Value::Object(_) | Value::Array(_) => None,
+    _ => Some(value.to_string())
+  }
+}
+
+fn tree_lines(value: &Value, prefix: &str, lines: &mut Vec<String>) {
+  let entries: Vec<(String, &Value)> = match value {
+    Value::Object(map) => map.iter().map(|(key, value)| (key.clone(), value)).collect(),
+    Value::Array(items) => items.iter().enumerate().map(|(i, value)| (format!("[{}]", i), value)).collect(),
+    _ => vec![]
+  };
+  let count = entries.len();
+  for (i, (key, child)) in entries.iter().enumerate() {
+    let last = i + 1 == count;
+    let connector = if last { "└── " } else { "├── " };
+    match tree_value(child) {
+      Some(value) => lines.push(format!("{}{}{}: {}", prefix, connector, key, value)),
+      None => {
+        lines.push(format!("{}{}{}", prefix, connector, key));
+        tree_lines(child, format!("{}{}", prefix, if last { "    " } else { "│   " }).as_str(), lines);
+      }
+    }
+  }
+}
+
+/// Renders the JSON document as a tree
+pub fn render_tree(value: &Value) -> String {
+  match tree_value(value) {
+    Some(value) => value,
+    None => {
+      let mut lines = vec![];
+      tree_lines(value, "", &mut lines);
+      lines.join("\n")
+    }
+  }
+}
+
+/// Handles the `pact-broker navigate` command
+pub async fn navigate(args: &ArgMatches) -> Result<(), CliError> {
+  let relations: Vec<String> = args.get_many::<String>("RELATION").map(|relations| relations.cloned().collect()).unwrap_or_default();
+  let params: Vec<String> = args.get_many::<String>("param").map(|params| params.cloned().collect()).unwrap_or_default();
+  let template_values = template_params(&params)?;
+  let hal_client = broker_client(args)?;
+
+  let resource = async {
+    let mut hal_client = hal_client.with_index().await?;
+    for relation in &relations {
+      hal_client = hal_client.navigate_to(relation, &template_values).await?;
+    }
+    Ok::<_, PactBrokerError>(hal_client.path_info.unwrap_or_default())
+  }.await?;
+
+  let document = if args.get_flag("links") {
+    resource.get("_links").cloned().unwrap_or_else(|| json!({}))
+  } else {
+    resource
+  };
+  let output = match args.get_one::<String>("output").map(|output| output.as_str()) {
+    Some("tree") => render_tree(&document),
+    Some("yaml") => serde_yaml::to_string(&document)
+      .map(|yaml| yaml.trim_end().to_string())
+      .map_err(|err| CliError::Failed(format!("Failed to render the output as YAML - {}", err)))?,
+    _ => serde_json::to_string_pretty(&document)
+      .map_err(|err| CliError::Failed(format!("Failed to render the output as JSON - {}", err)))?
+  };
+  println!("{}", output);
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+
+  use super::*;
+
+  #[test]
+  fn template_params_collects_repeated_names_into_a_list() {
+    let params = vec!["provider=Foo".to_string(), "tag=dev".to_string(), "tag=prod".to_string()];
+    expect!(template_params(&params).unwrap()).to(be_equal_to(HashMap::from([
+      ("provider".to_string(), TemplateValue::String("Foo".to_string())),
+      ("tag".to_string(), TemplateValue::List(vec!["dev".to_string(), "prod".to_string()]))
+    ])));
+    expect!(template_params(&["provider".to_string()]).map_err(|err| err.to_string())).to(be_err().value(
+      "Invalid parameter 'provider', expected NAME=VALUE".to_string()));
+  }
+
+  #[test]
+  fn render_tree_nests_objects_and_arrays() {
+    let links = json!({
+      "self": { "href": "http://localhost/" },
+      "pb:environments": [{ "name": "test", "href": "http://localhost/environments/1" }]
+    });
+    expect!(render_tree(&links)).to(be_equal_to([
+      "├── pb:environments",
+      "│   └── [0]",
+      "│       ├── href: http://localhost/environments/1",
+      "│       └── name: test",
+      "└── self",
+      "    └── href: http://localhost/"
+    ].join("\n")));
+  }
+}
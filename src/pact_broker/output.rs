@@ -0,0 +1,291 @@
+//! Renders resources from the Pact Broker in the output format selected with `--output`. Resources
+//! can be rendered as a table, JSON, YAML, CSV or a Markdown table. For the tabular formats, the
+//! columns can be selected with `--columns` and the header row left out with `--no-headers`.
+
+use clap::ArgMatches;
+use itertools::Itertools;
+use serde::{Serialize, Serializer};
+use serde_json::Value;
+use tabled::{builder::Builder, settings::Style};
+
+use crate::error::CliError;
+
+/// Output format selected with `--output`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+  /// Table with rounded borders
+  Table,
+  /// JSON document
+  Json,
+  /// YAML document
+  Yaml,
+  /// Comma separated values
+  Csv,
+  /// Markdown table
+  Markdown
+}
+
+impl OutputFormat {
+  /// Parses the output format from the `--output` argument. `text` is accepted as an alias for `table`.
+  pub fn parse(format: &str) -> Option<OutputFormat> {
+    match format.to_lowercase().as_str() {
+      "table" | "text" => Some(OutputFormat::Table),
+      "json" => Some(OutputFormat::Json),
+      "yaml" => Some(OutputFormat::Yaml),
+      "csv" => Some(OutputFormat::Csv),
+      "markdown" => Some(OutputFormat::Markdown),
+      _ => None
+    }
+  }
+}
+
+/// Options for rendering the output of a command
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputOptions {
+  /// Output format
+  pub format: OutputFormat,
+  /// Columns to include in the tabular formats, in order. All the columns are included if not set.
+  pub columns: Option<Vec<String>>,
+  /// If the header row should be included in the tabular formats
+  pub headers: bool
+}
+
+impl Default for OutputOptions {
+  fn default() -> Self {
+    OutputOptions {
+      format: OutputFormat::Table,
+      columns: None,
+      headers: true
+    }
+  }
+}
+
+impl OutputOptions {
+  /// Returns the output options from the `--output`, `--columns` and `--no-headers` arguments
+  pub fn from_args(args: &ArgMatches) -> OutputOptions {
+    OutputOptions {
+      format: args.try_get_one::<String>("output").ok().flatten()
+        .and_then(|format| OutputFormat::parse(format))
+        .unwrap_or(OutputFormat::Table),
+      columns: args.try_get_many::<String>("columns").ok().flatten()
+        .map(|columns| columns.cloned().collect()),
+      headers: !args.try_get_one::<bool>("no-headers").ok().flatten().copied().unwrap_or(false)
+    }
+  }
+}
+
+/// A resource that can be rendered in the tabular output formats
+pub trait Tabular {
+  /// Column headers
+  fn headers(&self) -> Vec<String>;
+  /// Rows of values, in the same order as the headers
+  fn rows(&self) -> Vec<Vec<String>>;
+}
+
+/// A typed resource that is displayed as a row in the tabular output formats
+pub trait TableRow {
+  /// Column headers
+  fn headers() -> Vec<&'static str>;
+  /// Values for the columns, in the same order as the headers
+  fn row(&self) -> Vec<String>;
+}
+
+/// Resource from the Pact Broker, with the typed items to display in the tabular formats. The JSON
+/// and YAML formats render the whole document as returned by the Pact Broker.
+#[derive(Debug, Clone)]
+pub struct ResourceTable<'a, T> {
+  document: &'a Value,
+  items: Vec<T>
+}
+
+impl <'a, T: TableRow> ResourceTable<'a, T> {
+  /// Table of the items in the document
+  pub fn new(document: &'a Value, items: Vec<T>) -> Self {
+    ResourceTable { document, items }
+  }
+
+  /// Table with a single row for the resource
+  pub fn single(document: &'a Value, item: T) -> Self {
+    ResourceTable { document, items: vec![item] }
+  }
+}
+
+impl <T> Serialize for ResourceTable<'_, T> {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    self.document.serialize(serializer)
+  }
+}
+
+impl <T: TableRow> Tabular for ResourceTable<'_, T> {
+  fn headers(&self) -> Vec<String> {
+    T::headers().iter().map(|header| header.to_string()).collect()
+  }
+
+  fn rows(&self) -> Vec<Vec<String>> {
+    self.items.iter().map(|item| item.row()).collect()
+  }
+}
+
+fn normalise_column_name(name: &str) -> String {
+  name.chars().filter(|c| c.is_alphanumeric()).collect::<String>().to_lowercase()
+}
+
+/// Selects the columns given with `--columns` from the headers and rows
+fn select_columns(headers: Vec<String>, rows: Vec<Vec<String>>, columns: Option<&Vec<String>>) -> Result<(Vec<String>, Vec<Vec<String>>), CliError> {
+  let columns = match columns {
+    Some(columns) if !columns.is_empty() => columns,
+    _ => return Ok((headers, rows))
+  };
+  let indices = columns.iter()
+    .map(|column| headers.iter()
+      .position(|header| normalise_column_name(header) == normalise_column_name(column))
+      .ok_or_else(|| CliError::InvalidArguments(format!("Unknown column '{}'. Available columns: {}",
+        column, headers.join(", ")))))
+    .collect::<Result<Vec<_>, _>>()?;
+  Ok((
+    indices.iter().map(|i| headers[*i].clone()).collect(),
+    rows.iter().map(|row| indices.iter().map(|i| row.get(*i).cloned().unwrap_or_default()).collect()).collect()
+  ))
+}
+
+fn csv_field(value: &str) -> String {
+  if value.contains([',', '"', '\n', '\r']) {
+    format!("\"{}\"", value.replace('"', "\"\""))
+  } else {
+    value.to_string()
+  }
+}
+
+fn markdown_row(values: &[String]) -> String {
+  format!("| {} |", values.iter().map(|value| value.replace('|', "\\|").replace('\n', " ")).join(" | "))
+}
+
+/// Renders the resource in the selected output format
+pub fn render<R: Serialize + Tabular>(resource: &R, options: &OutputOptions) -> Result<String, CliError> {
+  match options.format {
+    OutputFormat::Json => serde_json::to_string(resource)
+      .map_err(|err| CliError::Failed(format!("Failed to render the output as JSON - {}", err))),
+    OutputFormat::Yaml => serde_yaml::to_string(resource)
+      .map(|yaml| yaml.trim_end().to_string())
+      .map_err(|err| CliError::Failed(format!("Failed to render the output as YAML - {}", err))),
+    format => {
+      let (headers, rows) = select_columns(resource.headers(), resource.rows(), options.columns.as_ref())?;
+      Ok(match format {
+        OutputFormat::Csv => {
+          let header = if options.headers { Some(headers) } else { None };
+          header.iter().chain(rows.iter())
+            .map(|row| row.iter().map(|value| csv_field(value)).join(","))
+            .join("\n")
+        }
+        OutputFormat::Markdown => {
+          let mut lines = vec![];
+          if options.headers {
+            lines.push(markdown_row(&headers));
+            lines.push(format!("|{}|", headers.iter().map(|_| " --- ").join("|")));
+          }
+          lines.extend(rows.iter().map(|row| markdown_row(row)));
+          lines.join("\n")
+        }
+        _ => {
+          let mut builder = Builder::default();
+          if options.headers {
+            builder.push_record(headers);
+          }
+          for row in rows {
+            builder.push_record(row);
+          }
+          let mut table = builder.build();
+          if options.headers {
+            table.with(Style::rounded());
+          } else {
+            table.with(Style::rounded().remove_horizontals());
+          }
+          table.to_string()
+        }
+      })
+    }
+  }
+}
+
+/// Renders the resource in the selected output format and prints it
+pub fn print_output<R: Serialize + Tabular>(resource: &R, options: &OutputOptions) -> Result<(), CliError> {
+  println!("{}", render(resource, options)?);
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use super::*;
+
+  struct Environment {
+    name: String,
+    display_name: String,
+    production: bool
+  }
+
+  impl TableRow for Environment {
+    fn headers() -> Vec<&'static str> {
+      vec!["NAME", "DISPLAY NAME", "PRODUCTION"]
+    }
+
+    fn row(&self) -> Vec<String> {
+      vec![self.name.clone(), self.display_name.clone(), self.production.to_string()]
+    }
+  }
+
+  fn environment(name: &str, display_name: &str, production: bool) -> Environment {
+    Environment { name: name.to_string(), display_name: display_name.to_string(), production }
+  }
+
+  fn environments() -> Vec<Environment> {
+    vec![environment("test", "Test, UAT", false), environment("prod", "Prod | Live", true)]
+  }
+
+  fn options(format: OutputFormat) -> OutputOptions {
+    OutputOptions { format, .. OutputOptions::default() }
+  }
+
+  #[test]
+  fn render_as_csv_quotes_the_values() {
+    let document = json!({});
+    let table = ResourceTable::new(&document, environments());
+    expect!(render(&table, &options(OutputFormat::Csv))).to(be_ok().value(
+      "NAME,DISPLAY NAME,PRODUCTION\ntest,\"Test, UAT\",false\nprod,Prod | Live,true".to_string()));
+  }
+
+  #[test]
+  fn render_as_markdown_with_selected_columns_and_no_headers() {
+    let document = json!({});
+    let table = ResourceTable::new(&document, environments());
+    let options = OutputOptions {
+      format: OutputFormat::Markdown,
+      columns: Some(vec!["display-name".to_string(), "name".to_string()]),
+      headers: true
+    };
+    expect!(render(&table, &options)).to(be_ok().value(
+      "| DISPLAY NAME | NAME |\n| --- | --- |\n| Test, UAT | test |\n| Prod \\| Live | prod |".to_string()));
+    expect!(render(&table, &OutputOptions { headers: false, .. options.clone() })).to(be_ok().value(
+      "| Test, UAT | test |\n| Prod \\| Live | prod |".to_string()));
+  }
+
+  #[test]
+  fn render_fails_for_an_unknown_column() {
+    let document = json!({ "name": "test" });
+    let table = ResourceTable::single(&document, environment("test", "", false));
+    let options = OutputOptions { columns: Some(vec!["uuid".to_string()]), .. OutputOptions::default() };
+    expect!(render(&table, &options).map_err(|err| err.to_string())).to(be_err().value(
+      "Unknown column 'uuid'. Available columns: NAME, DISPLAY NAME, PRODUCTION".to_string()));
+  }
+
+  #[test]
+  fn render_as_json_or_yaml_outputs_the_whole_document() {
+    let document = json!({ "name": "test", "_links": {} });
+    let table = ResourceTable::single(&document, environment("test", "", false));
+    expect!(render(&table, &options(OutputFormat::Json))).to(be_ok().value(
+      "{\"_links\":{},\"name\":\"test\"}".to_string()));
+    expect!(render(&table, &options(OutputFormat::Yaml)).unwrap().contains("name: test")).to(be_true());
+  }
+}
@@ -0,0 +1,180 @@
+//! Functions for managing pacticipants using the `pb:pacticipants` relation
+
+use clap::ArgMatches;
+use maplit::hashmap;
+use serde_json::{json, Value};
+
+use crate::error::CliError;
+
+use super::{HALClient, PactBrokerError};
+use super::output::{print_output, OutputFormat, OutputOptions, ResourceTable, TableRow};
+use super::resources::{from_json, Pacticipant};
+use super::utils::{broker_client, pagination};
+
+impl TableRow for Pacticipant {
+  fn headers() -> Vec<&'static str> {
+    vec!["NAME", "DISPLAY NAME", "MAIN BRANCH", "REPOSITORY URL"]
+  }
+
+  fn row(&self) -> Vec<String> {
+    vec![
+      self.name.clone(),
+      self.display_name.clone().unwrap_or_default(),
+      self.main_branch.clone().unwrap_or_default(),
+      self.repository_url.clone().unwrap_or_default()
+    ]
+  }
+}
+
+/// Pacticipant with the timestamps, displayed when describing a pacticipant
+struct PacticipantDetails(Pacticipant);
+
+impl TableRow for PacticipantDetails {
+  fn headers() -> Vec<&'static str> {
+    let mut headers = Pacticipant::headers();
+    headers.extend(["CREATED AT", "UPDATED AT"]);
+    headers
+  }
+
+  fn row(&self) -> Vec<String> {
+    let mut row = self.0.row();
+    row.push(self.0.created_at.clone().unwrap_or_default());
+    row.push(self.0.updated_at.clone().unwrap_or_default());
+    row
+  }
+}
+
+/// Builds the pacticipant resource from the command arguments. Only the attributes provided are
+/// included, so that updating a pacticipant does not clear the other attributes.
+pub fn pacticipant_payload(args: &ArgMatches) -> Value {
+  let mut payload = json!({});
+  for (arg, attribute) in [("name", "name"), ("display-name", "displayName"),
+    ("main-branch", "mainBranch"), ("repository-url", "repositoryUrl")] {
+    if let Some(value) = args.get_one::<String>(arg) {
+      payload[attribute] = json!(value);
+    }
+  }
+  payload
+}
+
+/// Fetches the pacticipant with the given name using the `pb:pacticipant` relation
+pub async fn fetch_pacticipant(hal_client: &HALClient, name: &str) -> Result<HALClient, PactBrokerError> {
+  hal_client.clone()
+    .navigate("pb:pacticipant", &hashmap!{ "pacticipant".to_string() => name.to_string() })
+    .await
+    .map_err(|err| match err {
+      PactBrokerError::NotFound(_) => PactBrokerError::NotFound(format!("Pacticipant '{}' does not exist in the Pact Broker", name)),
+      _ => err
+    })
+}
+
+/// Handles the `pact-broker create-or-update-pacticipant` command. The pacticipant is created if it
+/// does not exist, otherwise the provided attributes are patched onto the existing pacticipant.
+pub async fn create_or_update_pacticipant(args: &ArgMatches) -> Result<(), CliError> {
+  let name = args.get_one::<String>("name").cloned().unwrap_or_default();
+  let hal_client = broker_client(args)?;
+  let payload = pacticipant_payload(args);
+
+  let result = async {
+    let hal_client = hal_client.with_index().await?;
+    match fetch_pacticipant(&hal_client, &name).await {
+      Ok(existing) => {
+        let url = existing.link_url("self", &hashmap!{})?;
+        existing.patch_json(url.as_str(), payload.to_string().as_str()).await.map(|pacticipant| (pacticipant, false))
+      }
+      Err(PactBrokerError::NotFound(_)) => {
+        let url = hal_client.link_url("pb:pacticipants", &hashmap!{})?;
+        hal_client.post_json(url.as_str(), payload.to_string().as_str()).await.map(|pacticipant| (pacticipant, true))
+      }
+      Err(err) => Err(err)
+    }
+  }.await;
+
+  match result {
+    Ok((pacticipant, created)) => {
+      let options = OutputOptions::from_args(args);
+      if options.format == OutputFormat::Table {
+        println!("Pacticipant \"{}\" {}", name, if created { "created" } else { "updated" });
+        Ok(())
+      } else {
+        print_output(&ResourceTable::single(&pacticipant, PacticipantDetails(from_json(&pacticipant)?)), &options)
+      }
+    }
+    Err(err) => Err(err.into())
+  }
+}
+
+/// Handles the `pact-broker describe-pacticipant` command
+pub async fn describe_pacticipant(args: &ArgMatches) -> Result<(), CliError> {
+  let name = args.get_one::<String>("name").cloned().unwrap_or_default();
+  let hal_client = broker_client(args)?;
+
+  match fetch_pacticipant(&hal_client, &name).await {
+    Ok(hal_client) => {
+      let pacticipant = hal_client.path_info.unwrap_or_default();
+      let details = PacticipantDetails(from_json(&pacticipant)?);
+      print_output(&ResourceTable::single(&pacticipant, details), &OutputOptions::from_args(args))
+    }
+    Err(err) => Err(err.into())
+  }
+}
+
+/// Handles the `pact-broker list-pacticipants` command. All the pages of pacticipants are fetched,
+/// up to the `--limit`.
+pub async fn list_pacticipants(args: &ArgMatches) -> Result<(), CliError> {
+  let hal_client = broker_client(args)?;
+
+  let result = async {
+    let hal_client = hal_client.with_index().await?;
+    let url = hal_client.link_url("pb:pacticipants", &hashmap!{})?;
+    hal_client.fetch_pages(url.as_str(), "pacticipants", &pagination(args)).await
+  }.await;
+
+  match result {
+    Ok(pacticipants) => {
+      let document = json!(pacticipants);
+      print_output(&ResourceTable::new(&document, from_json::<Vec<Pacticipant>>(&document)?), &OutputOptions::from_args(args))
+    }
+    Err(err) => Err(err.into())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use crate::cli::subcommand_args;
+  use crate::pact_broker::Pagination;
+  use crate::pact_broker::output::render;
+
+  use super::*;
+
+  #[test]
+  fn pacticipant_payload_only_includes_the_provided_attributes() {
+    let args = subcommand_args(&["pact-broker", "create-or-update-pacticipant",
+      "-b", "http://localhost", "--name", "Foo", "--main-branch", "main"]);
+    expect!(pacticipant_payload(&args)).to(be_equal_to(json!({
+      "name": "Foo",
+      "mainBranch": "main"
+    })));
+  }
+
+  #[test]
+  fn pacticipants_table_includes_the_main_branch() {
+    let pacticipants = vec![json!({ "name": "Foo", "mainBranch": "main" }), json!({ "name": "Bar" })];
+    let document = json!(pacticipants);
+    let table = render(&ResourceTable::new(&document, from_json::<Vec<Pacticipant>>(&document).unwrap()), &OutputOptions::default()).unwrap();
+    let lines: Vec<&str> = table.lines().collect();
+    expect!(lines.len()).to(be_equal_to(6));
+    expect!(lines[1].contains("MAIN BRANCH")).to(be_true());
+    expect!(lines[3].contains("Foo") && lines[3].contains("main")).to(be_true());
+  }
+
+  #[test]
+  fn list_pacticipants_reads_the_pagination_arguments() {
+    let args = subcommand_args(&["pact-broker", "list-pacticipants",
+      "-b", "http://localhost", "--limit", "10", "--page-size", "100"]);
+    expect!(pagination(&args)).to(be_equal_to(Pagination { page_size: Some(100), limit: Some(10) }));
+  }
+}
@@ -0,0 +1,81 @@
+//! Functions for listing the pacts in the Pact Broker
+
+use clap::ArgMatches;
+use maplit::hashmap;
+use serde_json::{json, Value};
+
+use crate::error::CliError;
+
+use super::PactBrokerError;
+use super::output::{print_output, OutputOptions, ResourceTable, TableRow};
+use super::resources::{from_json, PactVersion};
+use super::utils::{broker_client, pagination};
+
+impl TableRow for PactVersion {
+  fn headers() -> Vec<&'static str> {
+    vec!["CONSUMER", "CONSUMER_VERSION", "PROVIDER", "CREATED_AT"]
+  }
+
+  fn row(&self) -> Vec<String> {
+    vec![
+      self.embedded.consumer.name.clone(),
+      self.consumer_version().unwrap_or_default().to_string(),
+      self.embedded.provider.name.clone(),
+      self.created_at.clone().unwrap_or_default()
+    ]
+  }
+}
+
+/// Returns the table of the pacts from the `pb:latest-pact-versions` resource
+pub fn latest_pacts_table(response: &Value) -> Result<ResourceTable<'_, PactVersion>, PactBrokerError> {
+  let pacts = match response.get("pacts") {
+    Some(pacts) => from_json(pacts)?,
+    None => vec![]
+  };
+  Ok(ResourceTable::new(response, pacts))
+}
+
+/// Handles the `pact-broker list-latest-pact-versions` command. The pacts are fetched by following
+/// the `next` links, up to the `--limit`.
+pub async fn list_latest_pact_versions(args: &ArgMatches) -> Result<(), CliError> {
+  let hal_client = broker_client(args)?;
+  let result = async {
+    let hal_client = hal_client.with_index().await?;
+    let url = hal_client.link_url("pb:latest-pact-versions", &hashmap!{})?;
+    hal_client.fetch_pages(url.as_str(), "pacts", &pagination(args)).await
+  }.await;
+
+  match result {
+    Ok(pacts) => {
+      let response = json!({ "pacts": pacts });
+      print_output(&latest_pacts_table(&response)?, &OutputOptions::from_args(args))
+    }
+    Err(err) => Err(err.into())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use crate::pact_broker::output::Tabular;
+
+  use super::*;
+
+  #[test]
+  fn latest_pacts_table_lists_the_consumer_version_and_provider() {
+    let response = json!({
+      "pacts": [{
+        "createdAt": "2024-01-01T00:00:00+00:00",
+        "_embedded": {
+          "consumer": { "name": "Foo", "_embedded": { "version": { "number": "1.0.0" } } },
+          "provider": { "name": "Bar" }
+        }
+      }]
+    });
+    expect!(latest_pacts_table(&response).unwrap().rows()).to(be_equal_to(vec![
+      vec!["Foo".to_string(), "1.0.0".to_string(), "Bar".to_string(), "2024-01-01T00:00:00+00:00".to_string()]
+    ]));
+  }
+}
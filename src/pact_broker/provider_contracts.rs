@@ -0,0 +1,187 @@
+//! Publishes provider contracts (like OAS documents) to PactFlow for bi-directional contract testing
+
+use std::fs;
+use std::path::Path;
+
+use ansi_term::Colour::*;
+use clap::ArgMatches;
+use itertools::Itertools;
+use maplit::hashmap;
+use serde_json::{json, Value};
+
+use crate::error::CliError;
+
+use super::PactBrokerError;
+use super::output::{print_output, OutputFormat, OutputOptions, ResourceTable};
+use super::publish_pacts::published_contracts;
+use super::utils::{broker_client, display_notices};
+
+/// Returns the content type for the file from its extension
+pub fn content_type_for_file(path: &str) -> Option<&'static str> {
+  let extension = Path::new(path).extension()?.to_str()?.to_lowercase();
+  match extension.as_str() {
+    "yml" | "yaml" => Some("application/yaml"),
+    "json" => Some("application/json"),
+    "xml" => Some("application/xml"),
+    "txt" | "log" => Some("text/plain"),
+    _ => None
+  }
+}
+
+/// Determines if the self verification was successful. The exit code of the verification process
+/// takes precedence over the `--verification-success` and `--no-verification-success` flags.
+fn verification_success(args: &ArgMatches) -> Option<bool> {
+  if let Some(exit_code) = args.get_one::<i32>("verification-exit-code") {
+    Some(*exit_code == 0)
+  } else if args.get_flag("verification-success") {
+    Some(true)
+  } else if args.get_flag("no-verification-success") {
+    Some(false)
+  } else {
+    None
+  }
+}
+
+fn read_file(path: &str, description: &str) -> anyhow::Result<String> {
+  fs::read_to_string(path)
+    .map_err(|err| anyhow::anyhow!("Could not read the {} '{}' - {}", description, path, err))
+}
+
+/// Builds the request body for the `pf:publish-provider-contract` relation from the command arguments
+pub fn provider_contract_payload(args: &ArgMatches) -> anyhow::Result<Value> {
+  let contract_file = args.get_one::<String>("contract-file").cloned().unwrap_or_default();
+  let content_type = match args.get_one::<String>("content-type") {
+    Some(content_type) => content_type.clone(),
+    None => content_type_for_file(&contract_file)
+      .map(|content_type| content_type.to_string())
+      .ok_or_else(|| anyhow::anyhow!("Could not determine the content type of '{}', please specify it with --content-type", contract_file))?
+  };
+  let contents = read_file(&contract_file, "contract file")?;
+
+  let mut self_verification_results = json!({});
+  if let Some(success) = verification_success(args) {
+    self_verification_results["success"] = json!(success);
+  }
+  if let Some(results_file) = args.get_one::<String>("verification-results") {
+    self_verification_results["content"] = json!(base64::encode(read_file(results_file, "verification results")?));
+    let results_content_type = args.get_one::<String>("verification-results-content-type")
+      .cloned()
+      .or_else(|| content_type_for_file(results_file).map(|content_type| content_type.to_string()));
+    if let Some(results_content_type) = results_content_type {
+      self_verification_results["contentType"] = json!(results_content_type);
+    }
+  }
+  for (arg, attribute) in [("verification-results-format", "format"), ("verifier", "verifier"),
+    ("verifier-version", "verifierVersion")] {
+    if let Some(value) = args.get_one::<String>(arg) {
+      self_verification_results[attribute] = json!(value);
+    }
+  }
+
+  let mut contract = json!({
+    "content": base64::encode(contents),
+    "contentType": content_type,
+    "specification": args.get_one::<String>("specification").cloned().unwrap_or_else(|| "oas".to_string())
+  });
+  if self_verification_results.as_object().map(|attributes| !attributes.is_empty()).unwrap_or(false) {
+    contract["selfVerificationResults"] = self_verification_results;
+  }
+
+  let mut payload = json!({
+    "pacticipantVersionNumber": args.get_one::<String>("provider-app-version").cloned().unwrap_or_default(),
+    "tags": args.get_many::<String>("tag").map(|tags| tags.cloned().collect_vec()).unwrap_or_default(),
+    "contract": contract
+  });
+  if let Some(branch) = args.get_one::<String>("branch") {
+    payload["branch"] = json!(branch);
+  }
+  if let Some(build_url) = args.get_one::<String>("build-url") {
+    payload["buildUrl"] = json!(build_url);
+  }
+  Ok(payload)
+}
+
+/// Handles the `pactflow publish-provider-contract` command
+pub async fn publish_provider_contract(args: &ArgMatches) -> Result<(), CliError> {
+  let provider = args.get_one::<String>("provider").cloned().unwrap_or_default();
+  let version = args.get_one::<String>("provider-app-version").cloned().unwrap_or_default();
+  let options = OutputOptions::from_args(args);
+  let payload = provider_contract_payload(args).map_err(CliError::from)?;
+  let hal_client = broker_client(args)?;
+
+  let result = async {
+    let hal_client = hal_client.with_index().await?;
+    let url = hal_client.link_url("pf:publish-provider-contract", &hashmap!{ "provider".to_string() => provider.clone() })
+      .map_err(|_| PactBrokerError::LinkError("Can't publish the provider contract as there is no 'pf:publish-provider-contract' link. Please check that the broker is a PactFlow instance".to_string()))?;
+    hal_client.post_json(url.as_str(), payload.to_string().as_str()).await
+  }.await;
+
+  match result {
+    Ok(response) => {
+      if options.format != OutputFormat::Table {
+        print_output(&ResourceTable::new(&response, published_contracts(&response, &provider, &version)), &options)
+      } else if response.get("notices").is_some() {
+        display_notices(&response, args.get_flag("verbose"));
+        Ok(())
+      } else {
+        println!("{}", Green.paint(format!("Successfully published provider contract for {} version {} to PactFlow", provider, version)));
+        Ok(())
+      }
+    }
+    Err(err) => Err(err.into())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::io::Write;
+
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use crate::cli::subcommand_args;
+
+  use super::*;
+
+  #[test]
+  fn content_type_for_file_uses_the_file_extension() {
+    expect!(content_type_for_file("oas.yml")).to(be_some().value("application/yaml"));
+    expect!(content_type_for_file("oas.YAML")).to(be_some().value("application/yaml"));
+    expect!(content_type_for_file("oas.json")).to(be_some().value("application/json"));
+    expect!(content_type_for_file("oas")).to(be_none());
+  }
+
+  #[test]
+  fn provider_contract_payload_includes_the_self_verification_results() {
+    let dir = std::env::temp_dir().join(format!("pact_cli_provider_contract_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let contract_path = dir.join("oas.yaml");
+    fs::File::create(&contract_path).unwrap().write_all(b"openapi: 3.0.0").unwrap();
+    let results_path = dir.join("results.txt");
+    fs::File::create(&results_path).unwrap().write_all(b"passed").unwrap();
+
+    let args = subcommand_args(&["pactflow", "publish-provider-contract",
+      contract_path.to_str().unwrap(), "-b", "http://localhost", "--provider", "Bar", "-a", "1.0.0",
+      "--branch", "main", "-t", "dev", "--verification-exit-code", "1",
+      "--verification-results", results_path.to_str().unwrap(), "--verifier", "dredd"]);
+    let payload = provider_contract_payload(&args);
+    fs::remove_dir_all(&dir).unwrap();
+
+    expect!(payload.unwrap()).to(be_equal_to(json!({
+      "pacticipantVersionNumber": "1.0.0",
+      "branch": "main",
+      "tags": ["dev"],
+      "contract": {
+        "content": "b3BlbmFwaTogMy4wLjA=",
+        "contentType": "application/yaml",
+        "specification": "oas",
+        "selfVerificationResults": {
+          "success": false,
+          "content": "cGFzc2Vk",
+          "contentType": "text/plain",
+          "verifier": "dredd"
+        }
+      }
+    })));
+  }
+}
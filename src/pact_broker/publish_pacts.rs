@@ -0,0 +1,317 @@
+//! Publishes pact files to the Pact Broker using the `pb:publish-contracts` relation
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use clap::ArgMatches;
+use itertools::Itertools;
+use maplit::hashmap;
+use serde_json::{json, Value};
+use tracing::debug;
+
+use crate::error::CliError;
+
+use super::{HALClient, PactBrokerError};
+use super::output::{print_output, OutputFormat, OutputOptions, ResourceTable, TableRow};
+use super::utils::{broker_client, current_branch, current_build_url, current_commit, display_notices, json_str};
+
+/// Pact file loaded from disk, ready to be published
+#[derive(Debug, Clone)]
+pub struct PactFile {
+  /// Path the pact was loaded from
+  pub path: PathBuf,
+  /// Name of the consumer in the pact
+  pub consumer: String,
+  /// Name of the provider in the pact
+  pub provider: String,
+  /// Raw contents of the pact file
+  pub contents: String
+}
+
+/// Contract published to the Pact Broker, from the `pb:contracts` links of the publish response
+pub struct PublishedContract {
+  /// Name of the pacticipant that published the contract
+  pub pacticipant: String,
+  /// Version of the pacticipant
+  pub version: String,
+  /// Name of the contract
+  pub name: String,
+  /// URL of the contract in the Pact Broker
+  pub url: String
+}
+
+impl TableRow for PublishedContract {
+  fn headers() -> Vec<&'static str> {
+    vec!["PACTICIPANT", "VERSION", "CONTRACT", "URL"]
+  }
+
+  fn row(&self) -> Vec<String> {
+    vec![self.pacticipant.clone(), self.version.clone(), self.name.clone(), self.url.clone()]
+  }
+}
+
+/// Returns the contracts in the response to publishing contracts for the pacticipant version
+pub fn published_contracts(response: &Value, pacticipant: &str, version: &str) -> Vec<PublishedContract> {
+  let contract = |link: &Value| PublishedContract {
+    pacticipant: pacticipant.to_string(),
+    version: version.to_string(),
+    name: json_str(link, "/name").to_string(),
+    url: json_str(link, "/href").to_string()
+  };
+  match response.pointer("/_links/pb:contracts") {
+    Some(Value::Array(links)) => links.iter().map(contract).collect(),
+    Some(link @ Value::Object(_)) => vec![contract(link)],
+    _ => vec![]
+  }
+}
+
+/// Handles the `pact-broker publish` command
+pub async fn publish(args: &ArgMatches) -> Result<(), CliError> {
+  let version = consumer_version(args)?;
+  let (branch, build_url) = version_properties(args)?;
+  let mut tags = args.get_many::<String>("tag")
+    .map(|tags| tags.cloned().collect_vec())
+    .unwrap_or_default();
+  if args.get_flag("tag-with-git-branch") {
+    tags.push(current_branch().map_err(CliError::from)?);
+    tags = tags.into_iter().unique().collect();
+  }
+  let merge = args.get_flag("merge");
+  let verbose = args.get_flag("verbose");
+  let options = OutputOptions::from_args(args);
+
+  let paths = args.get_many::<String>("PACT_DIRS_OR_FILES")
+    .map(|paths| paths.cloned().collect_vec())
+    .unwrap_or_default();
+  let pacts = collect_pact_files(&paths)?;
+
+  let hal_client = broker_client(args)?;
+  let mut results = vec![];
+  let mut contracts = vec![];
+  for (consumer, pacts) in group_by_consumer(&pacts) {
+    let payload = publish_contracts_payload(&consumer, &version, branch.as_deref(), &tags,
+      build_url.as_deref(), &pacts, merge);
+    match publish_contracts(&hal_client, &payload).await {
+      Ok(response) => {
+        if options.format == OutputFormat::Table {
+          display_notices(&response, verbose);
+        }
+        contracts.extend(published_contracts(&response, &consumer, &version));
+        results.push(response);
+      }
+      Err(err) => return Err(CliError::broker(format!("Failed to publish pacts for consumer '{}'", consumer), err))
+    }
+  }
+
+  if options.format == OutputFormat::Table {
+    Ok(())
+  } else {
+    print_output(&ResourceTable::new(&Value::Array(results), contracts), &options)
+  }
+}
+
+/// Returns the consumer version. With `--auto-detect-version-properties`, the commit being built is
+/// used if the version is not given on the command line.
+fn consumer_version(args: &ArgMatches) -> Result<String, CliError> {
+  match args.get_one::<String>("consumer-app-version") {
+    Some(version) => Ok(version.clone()),
+    None if args.get_flag("auto-detect-version-properties") => current_commit()
+      .map_err(|err| CliError::InvalidArguments(format!("Could not detect the commit to use as the consumer version, please provide it with --consumer-app-version - {}", err))),
+    None => Err(CliError::InvalidArguments("Please specify the consumer version using --consumer-app-version".to_string()))
+  }
+}
+
+/// Returns the branch and build URL of the consumer version. With `--auto-detect-version-properties`,
+/// the ones not given on the command line are detected from the CI environment variables or git.
+fn version_properties(args: &ArgMatches) -> Result<(Option<String>, Option<String>), CliError> {
+  let branch = args.get_one::<String>("branch").cloned();
+  let build_url = args.get_one::<String>("build-url").cloned();
+  if args.get_flag("auto-detect-version-properties") {
+    let branch = match branch {
+      Some(branch) => branch,
+      None => current_branch()
+        .map_err(|err| CliError::InvalidArguments(format!("Could not detect the branch of the consumer version, please provide it with --branch - {}", err)))?
+    };
+    Ok((Some(branch), build_url.or_else(current_build_url)))
+  } else {
+    Ok((branch, build_url))
+  }
+}
+
+/// Posts the payload to the `pb:publish-contracts` relation of the Pact Broker index resource
+pub async fn publish_contracts(hal_client: &HALClient, payload: &Value) -> Result<Value, PactBrokerError> {
+  let hal_client = hal_client.clone().with_index().await?;
+  let href = hal_client.link_url("pb:publish-contracts", &hashmap!{})
+    .map_err(|_| PactBrokerError::LinkError("Can't publish pacts as there is no 'pb:publish-contracts' link. Please upgrade to Pact Broker version 2.86.0 or later".to_string()))?;
+  hal_client.post_json(href.as_str(), payload.to_string().as_str()).await
+}
+
+/// Expands the given directories, files and glob patterns into the list of pact files to publish
+pub fn collect_pact_files(paths: &[String]) -> anyhow::Result<Vec<PactFile>> {
+  let mut files = vec![];
+  for path in paths {
+    let p = Path::new(path);
+    if p.is_dir() {
+      let mut entries = fs::read_dir(p)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|entry| entry.is_file() && entry.extension().map(|ext| ext == "json").unwrap_or(false))
+        .collect_vec();
+      entries.sort();
+      files.extend(entries);
+    } else if p.is_file() {
+      files.push(p.to_path_buf());
+    } else {
+      let matches = glob::glob(path)?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.is_file())
+        .collect_vec();
+      if matches.is_empty() {
+        return Err(anyhow::anyhow!("No pact files found matching '{}'", path));
+      }
+      files.extend(matches);
+    }
+  }
+
+  if files.is_empty() {
+    return Err(anyhow::anyhow!("No pact files found in {}", paths.join(", ")));
+  }
+
+  files.iter().unique().map(|path| load_pact_file(path)).collect()
+}
+
+fn load_pact_file(path: &Path) -> anyhow::Result<PactFile> {
+  debug!("Loading pact file {}", path.display());
+  let contents = fs::read_to_string(path)?;
+  let json: Value = serde_json::from_str(&contents)
+    .map_err(|err| anyhow::anyhow!("Failed to parse pact file {} - {}", path.display(), err))?;
+  let consumer = json.pointer("/consumer/name").and_then(|name| name.as_str())
+    .ok_or_else(|| anyhow::anyhow!("Pact file {} does not have a consumer name", path.display()))?;
+  let provider = json.pointer("/provider/name").and_then(|name| name.as_str())
+    .ok_or_else(|| anyhow::anyhow!("Pact file {} does not have a provider name", path.display()))?;
+  Ok(PactFile {
+    path: path.to_path_buf(),
+    consumer: consumer.to_string(),
+    provider: provider.to_string(),
+    contents
+  })
+}
+
+/// Groups the pact files by consumer name, in the order the consumers were first seen
+pub fn group_by_consumer(pacts: &[PactFile]) -> Vec<(String, Vec<PactFile>)> {
+  pacts.iter()
+    .map(|pact| pact.consumer.clone())
+    .unique()
+    .map(|consumer| {
+      let consumer_pacts = pacts.iter().filter(|pact| pact.consumer == consumer).cloned().collect();
+      (consumer, consumer_pacts)
+    })
+    .collect()
+}
+
+/// Builds the request body for the `pb:publish-contracts` relation
+pub fn publish_contracts_payload(
+  consumer: &str,
+  version: &str,
+  branch: Option<&str>,
+  tags: &[String],
+  build_url: Option<&str>,
+  pacts: &[PactFile],
+  merge: bool
+) -> Value {
+  let contracts = pacts.iter().map(|pact| json!({
+    "consumerName": pact.consumer,
+    "providerName": pact.provider,
+    "specification": "pact",
+    "contentType": "application/json",
+    "content": base64::encode(&pact.contents),
+    "onConflict": if merge { "merge" } else { "overwrite" }
+  })).collect_vec();
+
+  let mut payload = json!({
+    "pacticipantName": consumer,
+    "pacticipantVersionNumber": version,
+    "tags": tags,
+    "contracts": contracts
+  });
+  if let Some(branch) = branch {
+    payload["branch"] = json!(branch);
+  }
+  if let Some(build_url) = build_url {
+    payload["buildUrl"] = json!(build_url);
+  }
+  payload
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use super::*;
+
+  fn pact_file(consumer: &str, provider: &str) -> PactFile {
+    PactFile {
+      path: PathBuf::from(format!("{}-{}.json", consumer, provider)),
+      consumer: consumer.to_string(),
+      provider: provider.to_string(),
+      contents: "{}".to_string()
+    }
+  }
+
+  #[test]
+  fn group_by_consumer_keeps_the_order_the_consumers_were_found() {
+    let pacts = vec![pact_file("B", "P1"), pact_file("A", "P1"), pact_file("B", "P2")];
+    let groups = group_by_consumer(&pacts);
+    let summary = groups.iter()
+      .map(|(consumer, pacts)| (consumer.as_str(), pacts.iter().map(|p| p.provider.as_str()).collect_vec()))
+      .collect_vec();
+    expect!(summary).to(be_equal_to(vec![("B", vec!["P1", "P2"]), ("A", vec!["P1"])]));
+  }
+
+  #[test]
+  fn publish_contracts_payload_includes_the_version_properties() {
+    let payload = publish_contracts_payload("Foo", "1.0.0", Some("main"), &["dev".to_string()],
+      Some("http://ci/1"), &[pact_file("Foo", "Bar")], true);
+    expect!(payload).to(be_equal_to(json!({
+      "pacticipantName": "Foo",
+      "pacticipantVersionNumber": "1.0.0",
+      "branch": "main",
+      "tags": ["dev"],
+      "buildUrl": "http://ci/1",
+      "contracts": [{
+        "consumerName": "Foo",
+        "providerName": "Bar",
+        "specification": "pact",
+        "contentType": "application/json",
+        "content": "e30=",
+        "onConflict": "merge"
+      }]
+    })));
+  }
+
+  #[test]
+  fn publish_contracts_payload_omits_the_optional_properties() {
+    let payload = publish_contracts_payload("Foo", "1.0.0", None, &[], None, &[pact_file("Foo", "Bar")], false);
+    expect!(payload.get("branch")).to(be_none());
+    expect!(payload.get("buildUrl")).to(be_none());
+    expect!(payload.pointer("/contracts/0/onConflict")).to(be_some().value(&json!("overwrite")));
+  }
+
+  #[test]
+  fn published_contracts_returns_a_row_for_each_contract_link() {
+    let response = json!({
+      "_links": {
+        "pb:contracts": [
+          { "name": "Pact between Foo (1.0.0) and Bar", "href": "http://localhost/pacts/1" },
+          { "name": "Pact between Foo (1.0.0) and Baz", "href": "http://localhost/pacts/2" }
+        ]
+      }
+    });
+    let rows = published_contracts(&response, "Foo", "1.0.0").iter().map(|contract| contract.row()).collect::<Vec<_>>();
+    expect!(rows).to(be_equal_to(vec![
+      vec!["Foo".to_string(), "1.0.0".to_string(), "Pact between Foo (1.0.0) and Bar".to_string(), "http://localhost/pacts/1".to_string()],
+      vec!["Foo".to_string(), "1.0.0".to_string(), "Pact between Foo (1.0.0) and Baz".to_string(), "http://localhost/pacts/2".to_string()]
+    ]));
+    expect!(published_contracts(&json!({ "_links": {} }), "Foo", "1.0.0").is_empty()).to(be_true());
+  }
+}
@@ -0,0 +1,143 @@
+//! Functions for recording releases of pacticipant versions to environments, used when several
+//! versions of an application (like a mobile app or library) can be supported at once
+
+use ansi_term::Colour::*;
+use clap::ArgMatches;
+use serde_json::{json, Value};
+
+use crate::error::CliError;
+
+use super::PactBrokerError;
+use super::deployments::{fetch_pacticipant_version, find_environment_link};
+use super::environments::find_environment_by_name;
+use super::output::{print_output, OutputFormat, OutputOptions, ResourceTable, TableRow};
+use super::resources::{embedded_items, from_json, HalResource, ReleasedVersion};
+use super::utils::broker_client;
+
+impl TableRow for ReleasedVersion {
+  fn headers() -> Vec<&'static str> {
+    vec!["UUID", "VERSION", "ENVIRONMENT", "CURRENTLY SUPPORTED", "CREATED AT", "SUPPORT ENDED AT"]
+  }
+
+  fn row(&self) -> Vec<String> {
+    vec![
+      self.uuid.clone(),
+      self.embedded.version.as_ref().map(|version| version.number.clone()).unwrap_or_default(),
+      self.embedded.environment.as_ref().map(|environment| environment.name.clone()).unwrap_or_default(),
+      self.currently_supported.to_string(),
+      self.created_at.clone().unwrap_or_default(),
+      self.support_ended_at.clone().unwrap_or_default()
+    ]
+  }
+}
+
+/// Returns the self links of the embedded released versions in the response
+pub fn released_version_links(response: &Value) -> Result<Vec<String>, PactBrokerError> {
+  Ok(embedded_items::<ReleasedVersion>(response, "releasedVersions")?
+    .iter()
+    .filter_map(|released_version| released_version.self_url())
+    .map(|href| href.to_string())
+    .collect())
+}
+
+/// Handles the `pact-broker record-release` command
+pub async fn record_release(args: &ArgMatches) -> Result<(), CliError> {
+  let pacticipant = args.get_one::<String>("pacticipant").cloned().unwrap_or_default();
+  let version = args.get_one::<String>("version").cloned().unwrap_or_default();
+  let environment = args.get_one::<String>("environment").cloned().unwrap_or_default();
+  let hal_client = broker_client(args)?;
+
+  let result = async {
+    let version_client = fetch_pacticipant_version(&hal_client, &pacticipant, &version).await?;
+    let version_resource = version_client.path_info.clone().unwrap_or_default();
+    let url = find_environment_link(&version_resource, "pb:record-release", &environment)?;
+    version_client.post_json(url.as_str(), "{}").await
+  }.await;
+
+  match result {
+    Ok(released_version) => {
+      let options = OutputOptions::from_args(args);
+      if options.format == OutputFormat::Table {
+        println!("Recorded release of {} version {} to {} environment in the Pact Broker.",
+          pacticipant, version, environment);
+        Ok(())
+      } else {
+        print_output(&ResourceTable::single(&released_version, from_json::<ReleasedVersion>(&released_version)?), &options)
+      }
+    }
+    Err(err) => Err(err.into())
+  }
+}
+
+/// Handles the `pact-broker record-support-ended` command. The currently supported releases of the
+/// pacticipant version in the environment are marked as no longer supported.
+pub async fn record_support_ended(args: &ArgMatches) -> Result<(), CliError> {
+  let pacticipant = args.get_one::<String>("pacticipant").cloned().unwrap_or_default();
+  let version = args.get_one::<String>("version").cloned().unwrap_or_default();
+  let environment = args.get_one::<String>("environment").cloned().unwrap_or_default();
+  let hal_client = broker_client(args)?;
+
+  let result = async {
+    let hal_client = hal_client.with_index().await?;
+    fetch_pacticipant_version(&hal_client, &pacticipant, &version).await?;
+    let environment_resource = find_environment_by_name(&hal_client, &environment).await?;
+    let released_versions_url = environment_resource.links.href("pb:currently-supported-released-versions")
+      .ok_or_else(|| PactBrokerError::LinkError(format!(
+        "Environment '{}' has no 'pb:currently-supported-released-versions' link. Please upgrade to Pact Broker version 2.80.0 or later", environment)))?;
+    let released_versions = hal_client.clone()
+      .fetch(format!("{}?pacticipant={}&version={}", released_versions_url,
+        urlencoding::encode(&pacticipant), urlencoding::encode(&version)).as_str())
+      .await?;
+    let released_version_urls = released_version_links(&released_versions)?;
+
+    if released_version_urls.is_empty() {
+      return Err(PactBrokerError::NotFound(format!("{} version {} is not currently released in {} environment. Cannot record support ended.",
+        pacticipant, version, environment)));
+    }
+
+    let mut results = vec![];
+    for url in released_version_urls {
+      results.push(hal_client.patch_json(url.as_str(), json!({ "currentlySupported": false }).to_string().as_str()).await?);
+    }
+    Ok(Value::Array(results))
+  }.await;
+
+  match result {
+    Ok(results) => {
+      let options = OutputOptions::from_args(args);
+      if options.format == OutputFormat::Table {
+        println!("{}", Green.paint(format!("Recorded support ended of {} version {} in {} environment in the Pact Broker.",
+          pacticipant, version, environment)));
+        Ok(())
+      } else {
+        print_output(&ResourceTable::new(&results, from_json::<Vec<ReleasedVersion>>(&results)?), &options)
+      }
+    }
+    Err(err) => Err(err.into())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use super::*;
+
+  #[test]
+  fn released_version_links_returns_the_self_link_of_each_released_version() {
+    let response = json!({
+      "_embedded": {
+        "releasedVersions": [
+          { "currentlySupported": true, "_links": { "self": { "href": "http://localhost/released-versions/1" } } },
+          { "currentlySupported": true, "_links": { "self": { "href": "http://localhost/released-versions/2" } } }
+        ]
+      }
+    });
+    expect!(released_version_links(&response).unwrap()).to(be_equal_to(vec![
+      "http://localhost/released-versions/1".to_string(),
+      "http://localhost/released-versions/2".to_string()
+    ]));
+    expect!(released_version_links(&json!({})).unwrap()).to(be_equal_to(Vec::<String>::new()));
+  }
+}
@@ -0,0 +1,511 @@
+//! Typed models of the resources returned by the Pact Broker
+//!
+//! The resources can be fetched with [`HALClient::fetch_typed`](super::HALClient::fetch_typed), or
+//! converted from the current resource of a client with
+//! [`HALClient::resource`](super::HALClient::resource). Only the attributes used by the CLI are
+//! modelled, any other attributes are ignored.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+
+use super::{Link, PactBrokerError};
+
+/// Links for a relation, which can be a single link or an array of links
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RelationLinks {
+  /// Single link
+  Single(Link),
+  /// Array of links (like the `pb:record-deployment` links for each environment)
+  Multiple(Vec<Link>)
+}
+
+/// HAL links of a resource, by relation
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Links(BTreeMap<String, RelationLinks>);
+
+impl Links {
+  /// Returns the links for the relation
+  pub fn links(&self, relation: &str) -> Vec<&Link> {
+    match self.0.get(relation) {
+      Some(RelationLinks::Single(link)) => vec![link],
+      Some(RelationLinks::Multiple(links)) => links.iter().collect(),
+      None => vec![]
+    }
+  }
+
+  /// Returns the link for the relation. If the relation has an array of links, the first one is returned.
+  pub fn link(&self, relation: &str) -> Option<&Link> {
+    self.links(relation).into_iter().next()
+  }
+
+  /// Returns the link for the relation with the given name
+  pub fn named(&self, relation: &str, name: &str) -> Option<&Link> {
+    self.links(relation).into_iter().find(|link| link.name == name)
+  }
+
+  /// Returns the HREF of the link for the relation
+  pub fn href(&self, relation: &str) -> Option<&str> {
+    self.link(relation).and_then(|link| link.href.as_deref())
+  }
+
+  /// Relations that have links
+  pub fn relations(&self) -> Vec<&str> {
+    self.0.keys().map(|relation| relation.as_str()).collect()
+  }
+}
+
+/// A resource with HAL links
+pub trait HalResource {
+  /// Links of the resource
+  fn links(&self) -> &Links;
+
+  /// URL of the resource, from the `self` link
+  fn self_url(&self) -> Option<&str> {
+    self.links().href("self")
+  }
+}
+
+macro_rules! hal_resource {
+  ($($resource:ty),*) => {
+    $(
+      impl HalResource for $resource {
+        fn links(&self) -> &Links {
+          &self.links
+        }
+      }
+    )*
+  }
+}
+
+/// Deserialises the resource from the JSON returned by the Pact Broker
+pub fn from_json<T: DeserializeOwned>(json: &Value) -> Result<T, PactBrokerError> {
+  serde_json::from_value(json.clone())
+    .map_err(|err| PactBrokerError::ContentError(format!("Could not read the resource from the Pact Broker - {}", err)))
+}
+
+/// Deserialises the items embedded in a collection resource under `_embedded.<key>`
+pub fn embedded_items<T: DeserializeOwned>(json: &Value, key: &str) -> Result<Vec<T>, PactBrokerError> {
+  match json.pointer(format!("/_embedded/{}", key).as_str()) {
+    Some(items) => from_json(items),
+    None => Ok(vec![])
+  }
+}
+
+/// An application that takes part in pacts
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Pacticipant {
+  /// Name
+  pub name: String,
+  /// Display name
+  pub display_name: Option<String>,
+  /// Main branch of the repository
+  pub main_branch: Option<String>,
+  /// Repository URL
+  pub repository_url: Option<String>,
+  /// When the pacticipant was created
+  pub created_at: Option<String>,
+  /// When the pacticipant was last updated
+  pub updated_at: Option<String>,
+  /// HAL links
+  #[serde(rename = "_links")]
+  pub links: Links
+}
+
+/// A branch of a pacticipant's repository
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Branch {
+  /// Branch name
+  pub name: String,
+  /// If this is the latest version for the branch (when embedded in a version)
+  pub latest: Option<bool>,
+  /// When the branch was created
+  pub created_at: Option<String>,
+  /// When the branch was last updated
+  pub updated_at: Option<String>,
+  /// HAL links
+  #[serde(rename = "_links")]
+  pub links: Links
+}
+
+/// A tag of a pacticipant version
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Tag {
+  /// Tag name
+  pub name: String,
+  /// When the tag was created
+  pub created_at: Option<String>,
+  /// HAL links
+  #[serde(rename = "_links")]
+  pub links: Links
+}
+
+/// Resources embedded in a pacticipant version
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct VersionEmbedded {
+  /// Branches the version is on
+  pub branch_versions: Vec<Branch>,
+  /// Tags of the version
+  pub tags: Vec<Tag>
+}
+
+/// A version of a pacticipant
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Version {
+  /// Version number
+  pub number: String,
+  /// URL of the build that created the version
+  pub build_url: Option<String>,
+  /// When the version was created
+  pub created_at: Option<String>,
+  /// Embedded branches and tags
+  #[serde(rename = "_embedded")]
+  pub embedded: VersionEmbedded,
+  /// HAL links
+  #[serde(rename = "_links")]
+  pub links: Links
+}
+
+impl Version {
+  /// Names of the branches the version is on
+  pub fn branch_names(&self) -> Vec<String> {
+    self.embedded.branch_versions.iter().map(|branch| branch.name.clone()).collect()
+  }
+
+  /// Names of the tags of the version
+  pub fn tag_names(&self) -> Vec<String> {
+    self.embedded.tags.iter().map(|tag| tag.name.clone()).collect()
+  }
+}
+
+/// Contact for an environment
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Contact {
+  /// Name of the contact (like a team)
+  pub name: String,
+  /// Contact details (like `emailAddress`)
+  pub details: Map<String, Value>
+}
+
+/// An environment that pacticipant versions are deployed or released to
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Environment {
+  /// UUID of the environment
+  pub uuid: String,
+  /// Name
+  pub name: String,
+  /// Display name
+  pub display_name: Option<String>,
+  /// If this is a production environment
+  pub production: bool,
+  /// Contacts for the environment
+  pub contacts: Vec<Contact>,
+  /// When the environment was created
+  pub created_at: Option<String>,
+  /// HAL links
+  #[serde(rename = "_links")]
+  pub links: Links
+}
+
+/// Version and environment embedded in deployed and released versions
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DeploymentEmbedded {
+  /// Pacticipant version that was deployed or released
+  pub version: Option<Version>,
+  /// Environment the version was deployed or released to
+  pub environment: Option<Environment>
+}
+
+/// A pacticipant version deployed to an environment
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DeployedVersion {
+  /// UUID of the deployment
+  pub uuid: String,
+  /// If the version is currently deployed
+  pub currently_deployed: bool,
+  /// Application instance the version is deployed to
+  pub application_instance: Option<String>,
+  /// Deprecated name for the application instance
+  pub target: Option<String>,
+  /// When the version was deployed
+  pub created_at: Option<String>,
+  /// When the version was undeployed
+  pub undeployed_at: Option<String>,
+  /// Embedded version and environment
+  #[serde(rename = "_embedded")]
+  pub embedded: DeploymentEmbedded,
+  /// HAL links
+  #[serde(rename = "_links")]
+  pub links: Links
+}
+
+impl DeployedVersion {
+  /// Application instance the version is deployed to
+  pub fn instance(&self) -> Option<&str> {
+    self.application_instance.as_deref().or(self.target.as_deref())
+  }
+
+  /// Number of the deployed version
+  pub fn version_number(&self) -> Option<&str> {
+    self.embedded.version.as_ref().map(|version| version.number.as_str())
+  }
+}
+
+/// A pacticipant version released to an environment
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ReleasedVersion {
+  /// UUID of the release
+  pub uuid: String,
+  /// If the version is currently supported
+  pub currently_supported: bool,
+  /// When the version was released
+  pub created_at: Option<String>,
+  /// When support for the version ended
+  pub support_ended_at: Option<String>,
+  /// Embedded version and environment
+  #[serde(rename = "_embedded")]
+  pub embedded: DeploymentEmbedded,
+  /// HAL links
+  #[serde(rename = "_links")]
+  pub links: Links
+}
+
+/// Event that triggers a webhook
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebhookEvent {
+  /// Event name (like `contract_content_changed`)
+  pub name: String
+}
+
+/// Consumer or provider a webhook applies to
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebhookPacticipant {
+  /// Pacticipant name
+  pub name: Option<String>,
+  /// Pacticipant label
+  pub label: Option<String>
+}
+
+/// Request a webhook executes
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebhookRequest {
+  /// HTTP method
+  pub method: String,
+  /// URL
+  pub url: String,
+  /// HTTP headers
+  pub headers: BTreeMap<String, String>,
+  /// Request body
+  pub body: Option<Value>,
+  /// Basic auth username
+  pub username: Option<String>
+}
+
+/// A webhook
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Webhook {
+  /// UUID of the webhook
+  pub uuid: Option<String>,
+  /// Description
+  pub description: Option<String>,
+  /// If the webhook is enabled
+  pub enabled: Option<bool>,
+  /// Consumer the webhook applies to
+  pub consumer: Option<WebhookPacticipant>,
+  /// Provider the webhook applies to
+  pub provider: Option<WebhookPacticipant>,
+  /// Events that trigger the webhook
+  pub events: Vec<WebhookEvent>,
+  /// Request the webhook executes
+  pub request: WebhookRequest,
+  /// HAL links
+  #[serde(rename = "_links")]
+  pub links: Links
+}
+
+impl Webhook {
+  /// UUID of the webhook, from the attribute or the end of the `self` link
+  pub fn webhook_uuid(&self) -> Option<String> {
+    self.uuid.clone()
+      .filter(|uuid| !uuid.is_empty())
+      .or_else(|| self.self_url().and_then(|url| url.rsplit('/').next()).map(|uuid| uuid.to_string()))
+  }
+}
+
+/// Pacticipant version in a matrix row
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MatrixVersion {
+  /// Version number
+  pub number: String
+}
+
+/// Consumer or provider in a matrix row
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MatrixPacticipant {
+  /// Pacticipant name
+  pub name: String,
+  /// Pacticipant version
+  pub version: Option<MatrixVersion>
+}
+
+/// Verification result in a matrix row
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MatrixVerificationResult {
+  /// If the verification was successful
+  pub success: Option<bool>,
+  /// When the verification was performed
+  pub verified_at: Option<String>,
+  /// HAL links
+  #[serde(rename = "_links")]
+  pub links: Links
+}
+
+/// Row of the matrix returned by the `can-i-deploy` query
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MatrixRow {
+  /// Consumer and its version
+  pub consumer: MatrixPacticipant,
+  /// Provider and its version
+  pub provider: MatrixPacticipant,
+  /// Verification result, if the pact has been verified
+  pub verification_result: Option<MatrixVerificationResult>
+}
+
+impl MatrixRow {
+  /// Consumer version number
+  pub fn consumer_version(&self) -> &str {
+    self.consumer.version.as_ref().map(|version| version.number.as_str()).unwrap_or_default()
+  }
+
+  /// Provider version number
+  pub fn provider_version(&self) -> &str {
+    self.provider.version.as_ref().map(|version| version.number.as_str()).unwrap_or_default()
+  }
+}
+
+/// Consumer or provider embedded in a pact version
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PactPacticipant {
+  /// Pacticipant name
+  pub name: String,
+  /// Embedded pacticipant version
+  #[serde(rename = "_embedded")]
+  pub embedded: Option<PactPacticipantEmbedded>
+}
+
+/// Version embedded in the consumer of a pact version
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PactPacticipantEmbedded {
+  /// Pacticipant version
+  pub version: Option<MatrixVersion>
+}
+
+/// Consumer and provider embedded in a pact version
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PactVersionEmbedded {
+  /// Consumer of the pact
+  pub consumer: PactPacticipant,
+  /// Provider of the pact
+  pub provider: PactPacticipant
+}
+
+/// A version of a pact between a consumer and provider
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PactVersion {
+  /// When the pact was published
+  pub created_at: Option<String>,
+  /// Embedded consumer and provider
+  #[serde(rename = "_embedded")]
+  pub embedded: PactVersionEmbedded,
+  /// HAL links
+  #[serde(rename = "_links")]
+  pub links: Links
+}
+
+impl PactVersion {
+  /// Consumer version number the pact was published for
+  pub fn consumer_version(&self) -> Option<&str> {
+    self.embedded.consumer.embedded.as_ref()
+      .and_then(|embedded| embedded.version.as_ref())
+      .map(|version| version.number.as_str())
+  }
+}
+
+hal_resource!(Pacticipant, Branch, Tag, Version, Environment, DeployedVersion, ReleasedVersion, Webhook,
+  MatrixVerificationResult, PactVersion);
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use super::*;
+
+  #[test]
+  fn version_from_json() {
+    let version: Version = from_json(&json!({
+      "number": "1.0.0",
+      "_embedded": {
+        "branchVersions": [{ "name": "main", "latest": true }],
+        "tags": [{ "name": "dev" }, { "name": "prod" }]
+      },
+      "_links": {
+        "self": { "href": "http://localhost/pacticipants/Foo/versions/1.0.0" },
+        "pb:record-release": [
+          { "name": "test", "href": "http://localhost/release/test" },
+          { "name": "production", "href": "http://localhost/release/production" }
+        ]
+      }
+    })).unwrap();
+
+    expect!(version.number.as_str()).to(be_equal_to("1.0.0"));
+    expect!(version.branch_names()).to(be_equal_to(vec!["main".to_string()]));
+    expect!(version.tag_names()).to(be_equal_to(vec!["dev".to_string(), "prod".to_string()]));
+    expect!(version.self_url()).to(be_some().value("http://localhost/pacticipants/Foo/versions/1.0.0"));
+    expect!(version.links.named("pb:record-release", "production").and_then(|link| link.href.clone()))
+      .to(be_some().value("http://localhost/release/production"));
+    expect!(version.links.links("pb:record-release").len()).to(be_equal_to(2));
+  }
+
+  #[test]
+  fn embedded_items_from_a_collection() {
+    let deployed_versions: Vec<DeployedVersion> = embedded_items(&json!({
+      "_embedded": {
+        "deployedVersions": [
+          { "uuid": "1", "currentlyDeployed": true, "target": "blue", "_embedded": { "version": { "number": "1.0.0" } } }
+        ]
+      }
+    }), "deployedVersions").unwrap();
+
+    expect!(deployed_versions.len()).to(be_equal_to(1));
+    expect!(deployed_versions[0].instance()).to(be_some().value("blue"));
+    expect!(deployed_versions[0].version_number()).to(be_some().value("1.0.0"));
+    expect!(embedded_items::<DeployedVersion>(&json!({}), "deployedVersions").unwrap().len()).to(be_equal_to(0));
+  }
+}
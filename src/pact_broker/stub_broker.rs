@@ -0,0 +1,135 @@
+//! Minimal HTTP server standing in for the Pact Broker in tests. Requests are matched on the method
+//! and path (including the query string) and answered with a fixed response. Every request received
+//! is recorded, so tests can check what was sent and how many pages were fetched.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use serde_json::Value;
+
+/// Response returned for the requests that match the method and path
+#[derive(Debug, Clone)]
+pub struct Route {
+  method: String,
+  path: String,
+  status: u16,
+  headers: Vec<(String, String)>,
+  body: Option<Value>
+}
+
+impl Route {
+  /// Route that responds with the status and no body
+  pub fn new(method: &str, path: &str, status: u16) -> Route {
+    Route { method: method.to_string(), path: path.to_string(), status, headers: vec![], body: None }
+  }
+
+  /// Route for a GET request that responds with the HAL document
+  pub fn get(path: &str, body: Value) -> Route {
+    Route::new("GET", path, 200).with_body(body)
+  }
+
+  /// Responds with the JSON body, using the HAL content type
+  pub fn with_body(self, body: Value) -> Route {
+    Route { body: Some(body), .. self }
+  }
+
+  /// Adds the header to the response
+  pub fn with_header(mut self, name: &str, value: &str) -> Route {
+    self.headers.push((name.to_string(), value.to_string()));
+    self
+  }
+}
+
+/// Request received by the stub
+#[derive(Debug, Clone, PartialEq)]
+pub struct Request {
+  /// HTTP method
+  pub method: String,
+  /// Path and query string
+  pub path: String,
+  /// Request body
+  pub body: String
+}
+
+/// Running stub server. Requests that do not match any route get a `404 Not Found` response.
+pub struct StubBroker {
+  /// Base URL of the server
+  pub url: String,
+  requests: Arc<Mutex<Vec<Request>>>
+}
+
+impl StubBroker {
+  /// Starts the server on a free port, serving the routes until the test process exits
+  pub fn start(routes: Vec<Route>) -> StubBroker {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let requests = Arc::new(Mutex::new(vec![]));
+    let recorded = requests.clone();
+    thread::spawn(move || {
+      for stream in listener.incoming().flatten() {
+        if let Some(request) = read_request(&stream) {
+          let route = routes.iter().find(|route| route.method == request.method && route.path == request.path);
+          recorded.lock().unwrap().push(request);
+          write_response(stream, route);
+        }
+      }
+    });
+    StubBroker { url, requests }
+  }
+
+  /// Requests received so far, in order
+  pub fn requests(&self) -> Vec<Request> {
+    self.requests.lock().unwrap().clone()
+  }
+
+  /// Paths of the GET requests received so far, in order
+  pub fn fetched_paths(&self) -> Vec<String> {
+    self.requests().iter()
+      .filter(|request| request.method == "GET")
+      .map(|request| request.path.clone())
+      .collect()
+  }
+}
+
+fn read_request(stream: &TcpStream) -> Option<Request> {
+  let mut reader = BufReader::new(stream);
+  let mut request_line = String::new();
+  reader.read_line(&mut request_line).ok()?;
+  let mut parts = request_line.split_whitespace();
+  let method = parts.next()?.to_string();
+  let path = parts.next()?.to_string();
+
+  let mut content_length = 0;
+  loop {
+    let mut line = String::new();
+    if reader.read_line(&mut line).ok()? == 0 || line.trim().is_empty() {
+      break;
+    }
+    if let Some((name, value)) = line.split_once(':') {
+      if name.trim().eq_ignore_ascii_case("content-length") {
+        content_length = value.trim().parse().unwrap_or_default();
+      }
+    }
+  }
+  let mut body = vec![0; content_length];
+  reader.read_exact(&mut body).ok()?;
+  Some(Request { method, path, body: String::from_utf8_lossy(&body).to_string() })
+}
+
+fn write_response(mut stream: TcpStream, route: Option<&Route>) {
+  let status = route.map(|route| route.status).unwrap_or(404);
+  let body = route.and_then(|route| route.body.as_ref()).map(|body| body.to_string());
+  let mut response = format!("HTTP/1.1 {} Stub\r\nConnection: close\r\nContent-Length: {}\r\n",
+    status, body.as_ref().map(|body| body.len()).unwrap_or_default());
+  if body.is_some() {
+    response.push_str("Content-Type: application/hal+json\r\n");
+  }
+  for (name, value) in route.map(|route| route.headers.clone()).unwrap_or_default() {
+    response.push_str(format!("{}: {}\r\n", name, value).as_str());
+  }
+  response.push_str("\r\n");
+  response.push_str(body.unwrap_or_default().as_str());
+  let _ = stream.write_all(response.as_bytes());
+}
@@ -0,0 +1,302 @@
+//! Expansion of URI templates ([RFC 6570](https://www.rfc-editor.org/rfc/rfc6570)) in the HREFs of
+//! templated HAL links. All four levels of the RFC are supported: simple and reserved expansion,
+//! the `#`, `.`, `/`, `;`, `?` and `&` operators, and the prefix (`{name:3}`) and explode
+//! (`{name*}`) modifiers for string, list and associative array values.
+
+use std::collections::HashMap;
+
+use itertools::Itertools;
+
+use super::PactBrokerError;
+
+/// Value of a variable in a URI template
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateValue {
+  /// String value
+  String(String),
+  /// List of values
+  List(Vec<String>),
+  /// Associative array of names and values, in order
+  Map(Vec<(String, String)>)
+}
+
+impl TemplateValue {
+  /// If the value is undefined for the expansion. Empty lists and associative arrays are undefined.
+  fn is_undefined(&self) -> bool {
+    match self {
+      TemplateValue::String(_) => false,
+      TemplateValue::List(values) => values.is_empty(),
+      TemplateValue::Map(values) => values.is_empty()
+    }
+  }
+}
+
+impl From<&str> for TemplateValue {
+  fn from(value: &str) -> Self {
+    TemplateValue::String(value.to_string())
+  }
+}
+
+impl From<String> for TemplateValue {
+  fn from(value: String) -> Self {
+    TemplateValue::String(value)
+  }
+}
+
+impl From<Vec<String>> for TemplateValue {
+  fn from(values: Vec<String>) -> Self {
+    TemplateValue::List(values)
+  }
+}
+
+impl From<Vec<(String, String)>> for TemplateValue {
+  fn from(values: Vec<(String, String)>) -> Self {
+    TemplateValue::Map(values)
+  }
+}
+
+/// Expansion rules for an expression operator (RFC 6570 Appendix A)
+struct Operator {
+  first: &'static str,
+  separator: &'static str,
+  named: bool,
+  if_empty: &'static str,
+  allow_reserved: bool
+}
+
+impl Operator {
+  fn parse(expression: &str) -> Result<(Operator, &str), String> {
+    let operator = |first, separator, named, if_empty, allow_reserved| Operator { first, separator, named, if_empty, allow_reserved };
+    let mut chars = expression.chars();
+    Ok(match chars.next() {
+      Some('+') => (operator("", ",", false, "", true), chars.as_str()),
+      Some('#') => (operator("#", ",", false, "", true), chars.as_str()),
+      Some('.') => (operator(".", ".", false, "", false), chars.as_str()),
+      Some('/') => (operator("/", "/", false, "", false), chars.as_str()),
+      Some(';') => (operator(";", ";", true, "", false), chars.as_str()),
+      Some('?') => (operator("?", "&", true, "=", false), chars.as_str()),
+      Some('&') => (operator("&", "&", true, "=", false), chars.as_str()),
+      Some(ch) if "=,!@|".contains(ch) => return Err(format!("operator '{}' is reserved", ch)),
+      _ => (operator("", ",", false, "", false), expression)
+    })
+  }
+}
+
+/// Variable in an expression, with its modifier
+struct VarSpec<'a> {
+  name: &'a str,
+  prefix: Option<usize>,
+  explode: bool
+}
+
+impl <'a> VarSpec<'a> {
+  fn parse(spec: &'a str) -> Result<VarSpec<'a>, String> {
+    let (name, prefix, explode) = if let Some(name) = spec.strip_suffix('*') {
+      (name, None, true)
+    } else if let Some((name, prefix)) = spec.split_once(':') {
+      let prefix = prefix.parse::<usize>().ok()
+        .filter(|prefix| *prefix > 0 && *prefix < 10000)
+        .ok_or_else(|| format!("invalid prefix modifier in '{}'", spec))?;
+      (name, Some(prefix), false)
+    } else {
+      (spec, None, false)
+    };
+    let valid_name = !name.is_empty() && name.split('.').all(|part| !part.is_empty() &&
+      part.chars().all(|ch| ch.is_ascii_alphanumeric() || ch == '_' || ch == '%'));
+    if valid_name {
+      Ok(VarSpec { name, prefix, explode })
+    } else {
+      Err(format!("invalid variable name '{}'", name))
+    }
+  }
+}
+
+/// Percent-encodes the value. Unreserved characters are always kept, and reserved characters and
+/// existing percent-encoded triplets are also kept when reserved expansion is allowed.
+fn encode(value: &str, allow_reserved: bool) -> String {
+  let chars = value.chars().collect_vec();
+  let mut result = String::new();
+  for (i, ch) in chars.iter().enumerate() {
+    let pct_encoded = *ch == '%' && chars.get(i + 1).is_some_and(|ch| ch.is_ascii_hexdigit()) &&
+      chars.get(i + 2).is_some_and(|ch| ch.is_ascii_hexdigit());
+    if ch.is_ascii_alphanumeric() || "-._~".contains(*ch) ||
+      (allow_reserved && (":/?#[]@!$&'()*+,;=".contains(*ch) || pct_encoded)) {
+      result.push(*ch);
+    } else {
+      let mut buffer = [0; 4];
+      for byte in ch.encode_utf8(&mut buffer).bytes() {
+        result.push_str(format!("%{:02X}", byte).as_str());
+      }
+    }
+  }
+  result
+}
+
+fn expand_expression(expression: &str, values: &HashMap<String, TemplateValue>) -> Result<String, String> {
+  let (operator, variables) = Operator::parse(expression)?;
+  let mut expanded = vec![];
+  for spec in variables.split(',') {
+    let spec = VarSpec::parse(spec)?;
+    let value = match values.get(spec.name) {
+      Some(value) if !value.is_undefined() => value,
+      _ => continue
+    };
+    let named = |value: &str| if operator.named {
+      if value.is_empty() {
+        format!("{}{}", spec.name, operator.if_empty)
+      } else {
+        format!("{}={}", spec.name, value)
+      }
+    } else {
+      value.to_string()
+    };
+    expanded.push(match value {
+      TemplateValue::String(value) => {
+        let value = match spec.prefix {
+          Some(prefix) => value.chars().take(prefix).collect(),
+          None => value.clone()
+        };
+        named(encode(&value, operator.allow_reserved).as_str())
+      }
+      TemplateValue::List(items) => if spec.explode {
+        items.iter()
+          .map(|item| named(encode(item, operator.allow_reserved).as_str()))
+          .join(operator.separator)
+      } else {
+        named(items.iter().map(|item| encode(item, operator.allow_reserved)).join(",").as_str())
+      }
+      TemplateValue::Map(pairs) => if spec.explode {
+        pairs.iter()
+          .map(|(key, value)| {
+            let key = encode(key, operator.allow_reserved);
+            if operator.named && value.is_empty() {
+              format!("{}{}", key, operator.if_empty)
+            } else {
+              format!("{}={}", key, encode(value, operator.allow_reserved))
+            }
+          })
+          .join(operator.separator)
+      } else {
+        named(pairs.iter()
+          .map(|(key, value)| format!("{},{}", encode(key, operator.allow_reserved), encode(value, operator.allow_reserved)))
+          .join(",").as_str())
+      }
+    });
+  }
+  if expanded.is_empty() {
+    Ok(String::new())
+  } else {
+    Ok(format!("{}{}", operator.first, expanded.join(operator.separator)))
+  }
+}
+
+/// Expands the URI template with the values. Variables without a value are removed from the URI,
+/// as the RFC requires.
+pub fn expand(template: &str, values: &HashMap<String, TemplateValue>) -> Result<String, PactBrokerError> {
+  let malformed = |message: String| PactBrokerError::LinkError(
+    format!("Malformed URI template '{}' - {}", template, message));
+  let mut result = String::new();
+  let mut remaining = template;
+  while let Some(start) = remaining.find('{') {
+    result.push_str(&remaining[..start]);
+    let end = remaining[start..].find('}')
+      .ok_or_else(|| malformed("expression is not closed".to_string()))?;
+    let expression = &remaining[start + 1..start + end];
+    result.push_str(expand_expression(expression, values).map_err(malformed)?.as_str());
+    remaining = &remaining[start + end + 1..];
+  }
+  result.push_str(remaining);
+  Ok(result)
+}
+
+/// Returns the names of the variables in the simple, reserved and path expansions of the template
+/// that have no value. The RFC removes them, which leaves an empty path segment in the URI.
+pub fn undefined_variables(template: &str, values: &HashMap<String, TemplateValue>) -> Vec<String> {
+  template.split('{').skip(1)
+    .filter_map(|part| part.split_once('}').map(|(expression, _)| expression))
+    .filter_map(|expression| Operator::parse(expression).ok())
+    .filter(|(operator, _)| operator.first.is_empty() || operator.first == "/")
+    .flat_map(|(_, variables)| variables.split(','))
+    .filter_map(|spec| VarSpec::parse(spec).ok())
+    .filter(|spec| values.get(spec.name).is_none_or(|value| value.is_undefined()))
+    .map(|spec| spec.name.to_string())
+    .unique()
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use maplit::hashmap;
+
+  use super::*;
+
+  fn values() -> HashMap<String, TemplateValue> {
+    hashmap!{
+      "var".to_string() => "value".into(),
+      "hello".to_string() => "Hello World!".into(),
+      "path".to_string() => "/foo/bar".into(),
+      "empty".to_string() => "".into(),
+      "list".to_string() => vec!["red".to_string(), "green".to_string(), "blue".to_string()].into(),
+      "keys".to_string() => vec![
+        ("semi".to_string(), ";".to_string()),
+        ("dot".to_string(), ".".to_string()),
+        ("comma".to_string(), ",".to_string())
+      ].into()
+    }
+  }
+
+  #[test]
+  fn expands_the_examples_from_the_rfc() {
+    let values = values();
+    for (template, expected) in [
+      ("{var}", "value"),
+      ("{hello}", "Hello%20World%21"),
+      ("{+path}/here", "/foo/bar/here"),
+      ("{+hello}", "Hello%20World!"),
+      ("{#var}", "#value"),
+      ("X{.var}", "X.value"),
+      ("{/var,empty}", "/value/"),
+      ("{;var,empty}", ";var=value;empty"),
+      ("{?var,empty}", "?var=value&empty="),
+      ("?fixed=yes{&var}", "?fixed=yes&var=value"),
+      ("{var:3}", "val"),
+      ("{list}", "red,green,blue"),
+      ("{list*}", "red,green,blue"),
+      ("{/list*}", "/red/green/blue"),
+      ("{?list*}", "?list=red&list=green&list=blue"),
+      ("{keys}", "semi,%3B,dot,.,comma,%2C"),
+      ("{?keys*}", "?semi=%3B&dot=.&comma=%2C"),
+      ("{+keys*}", "semi=;,dot=.,comma=,")
+    ] {
+      expect!(expand(template, &values)).to(be_ok().value(expected.to_string()));
+    }
+  }
+
+  #[test]
+  fn removes_undefined_variables() {
+    let values = values();
+    expect!(expand("/pacticipants{?q*}", &values)).to(be_ok().value("/pacticipants".to_string()));
+    expect!(expand("/pacticipants/{var}/versions{?var,undef}", &values))
+      .to(be_ok().value("/pacticipants/value/versions?var=value".to_string()));
+    expect!(expand("{/var,undef}", &values)).to(be_ok().value("/value".to_string()));
+  }
+
+  #[test]
+  fn undefined_variables_only_returns_the_variables_of_path_segments() {
+    let values = values();
+    expect!(undefined_variables("/pacticipants/{pacticipant}/versions/{var}{?undef}", &values))
+      .to(be_equal_to(vec!["pacticipant".to_string()]));
+    expect!(undefined_variables("{+base}{/list*,segment}{;matrix}{#fragment}", &values))
+      .to(be_equal_to(vec!["base".to_string(), "segment".to_string()]));
+  }
+
+  #[test]
+  fn fails_for_malformed_templates() {
+    let values = values();
+    expect!(expand("/pacticipants/{pacticipant", &values)).to(be_err().value(
+      "Malformed URI template '/pacticipants/{pacticipant' - expression is not closed"));
+    expect!(expand("/{=var}", &values)).to(be_err());
+    expect!(expand("/{var:0}", &values)).to(be_err());
+  }
+}
@@ -1,67 +1,393 @@
 //! Utility functions
 
 use std::panic::RefUnwindSafe;
-use std::time::Duration;
+use std::error::Error;
+use std::io::ErrorKind;
+use std::time::{Duration, SystemTime};
 
-use futures::StreamExt;
+use ansi_term::Colour::*;
+use pact_models::http_utils::HttpAuth;
 use pact_models::interaction::Interaction;
-use reqwest::RequestBuilder;
+use reqwest::{RequestBuilder, StatusCode};
+use serde_json::Value;
 use tokio::time::sleep;
 use tracing::{trace, warn};
 
-/// Retries a request on failure
-pub(crate) async fn with_retries(retries: u8, request: RequestBuilder) -> Result<reqwest::Response, reqwest::Error> {
-  match &request.try_clone() {
-    None => {
-      warn!("with_retries: Could not retry the request as it is not cloneable");
-      request.send().await
+use crate::error::CliError;
+
+use super::{HALClient, Pagination, TlsOptions};
+use super::cache::ResponseCache;
+use super::config::{config_path, selected_profile, BrokerProfile};
+
+fn arg_value(args: &clap::ArgMatches, id: &str) -> Option<String> {
+  args.try_get_one::<String>(id).ok().flatten().cloned()
+}
+
+/// Returns the Pact Broker base URL. The `--broker-base-url` argument (or `PACT_BROKER_BASE_URL`
+/// environment variable) takes precedence over the URL from the selected profile.
+pub fn get_broker_url(args: &clap::ArgMatches) -> anyhow::Result<String> {
+  if let Some(url) = arg_value(args, "broker-base-url") {
+    return Ok(url);
+  }
+  selected_profile(args)?
+    .and_then(|profile| profile.broker_base_url)
+    .ok_or_else(|| anyhow::anyhow!("The Pact Broker base URL is required. Provide it with --broker-base-url, the PACT_BROKER_BASE_URL environment variable or a profile in {}",
+      config_path().map(|path| path.display().to_string()).unwrap_or_else(|| "~/.config/pact/config.toml".to_string())))
+}
+
+fn auth_from(token: Option<String>, username: Option<String>, password: Option<String>) -> Option<HttpAuth> {
+  match (token, username) {
+    (Some(token), _) => Some(HttpAuth::Token(token)),
+    (None, Some(username)) => Some(HttpAuth::User(username, password)),
+    (None, None) => None
+  }
+}
+
+/// Returns the credentials from the profile. They are only used with the broker URL from the same
+/// profile, unless the profile was selected explicitly, so they are not sent to a broker URL given
+/// with `--broker-base-url` (or `PACT_BROKER_BASE_URL`).
+fn profile_auth(profile: BrokerProfile, url_from_args: bool, profile_selected: bool) -> Option<HttpAuth> {
+  if url_from_args && !profile_selected {
+    None
+  } else {
+    auth_from(profile.broker_token, profile.broker_username, profile.broker_password)
+  }
+}
+
+/// Sets up the broker credentials. Credentials from the command arguments (or their environment
+/// variables) take precedence, and the credentials from the selected profile are only used if none
+/// were provided.
+pub fn get_auth(args: &clap::ArgMatches) -> anyhow::Result<HttpAuth> {
+  let auth = auth_from(arg_value(args, "broker-token"), arg_value(args, "broker-username"),
+    arg_value(args, "broker-password"));
+  match auth {
+    Some(auth) => Ok(auth),
+    None => Ok(selected_profile(args)?
+      .and_then(|profile| profile_auth(profile, arg_value(args, "broker-base-url").is_some(),
+        arg_value(args, "profile").is_some()))
+      .unwrap_or(HttpAuth::None))
+  }
+}
+
+/// Creates the client for the Pact Broker from the command arguments, failing if the broker URL or
+/// credentials can not be resolved
+pub(crate) fn broker_client(args: &clap::ArgMatches) -> Result<HALClient, CliError> {
+  let url = get_broker_url(args)?;
+  let auth = get_auth(args)?;
+  let tls = tls_options(args)?;
+  let timeout = args.try_get_one::<u64>("timeout").ok().flatten().map(|seconds| Duration::from_secs(*seconds));
+  Ok(HALClient::with_url(&url, Some(auth))
+    .with_connection(&tls, timeout)
+    .map_err(|err| CliError::InvalidArguments(format!("Invalid TLS settings for the Pact Broker - {}", err)))?
+    .with_retry_policy(retry_policy(args))
+    .with_cache(response_cache(args)))
+}
+
+/// Returns the response cache from the `--offline` and `--no-cache` arguments
+pub(crate) fn response_cache(args: &clap::ArgMatches) -> Option<ResponseCache> {
+  let flag = |id: &str| args.try_get_one::<bool>(id).ok().flatten().copied().unwrap_or(false);
+  if flag("no-cache") {
+    None
+  } else {
+    ResponseCache::default_dir().map(|dir| ResponseCache::new(dir).offline(flag("offline")))
+  }
+}
+
+/// Returns the retry policy from the `--max-retries` and `--retry-backoff` arguments
+pub(crate) fn retry_policy(args: &clap::ArgMatches) -> RetryPolicy {
+  let default = RetryPolicy::default();
+  RetryPolicy {
+    max_retries: args.try_get_one::<u8>("max-retries").ok().flatten().copied().unwrap_or(default.max_retries),
+    backoff: args.try_get_one::<u64>("retry-backoff").ok().flatten()
+      .map(|millis| Duration::from_millis(*millis))
+      .unwrap_or(default.backoff),
+    .. default
+  }
+}
+
+fn read_pem_file(args: &clap::ArgMatches, id: &str) -> Result<Option<Vec<u8>>, CliError> {
+  arg_value(args, id)
+    .map(|path| std::fs::read(&path)
+      .map_err(|err| CliError::InvalidArguments(format!("Could not read --{} file '{}' - {}", id, path, err))))
+    .transpose()
+}
+
+/// Returns the TLS settings from the `--ssl-cert-file`, `--client-cert`, `--client-key` and
+/// `--insecure-tls` arguments. The client certificate and key are combined into one PEM identity.
+pub(crate) fn tls_options(args: &clap::ArgMatches) -> Result<TlsOptions, CliError> {
+  let client_identity = match (read_pem_file(args, "client-cert")?, read_pem_file(args, "client-key")?) {
+    (Some(mut cert), Some(key)) => {
+      cert.push(b'\n');
+      cert.extend(key);
+      Some(cert)
+    }
+    _ => None
+  };
+  Ok(TlsOptions {
+    ca_certificates: read_pem_file(args, "ssl-cert-file")?,
+    client_identity,
+    insecure: args.try_get_one::<bool>("insecure-tls").ok().flatten().copied().unwrap_or(false)
+  })
+}
+
+/// Returns the pagination options from the `--page-size` and `--limit` arguments
+pub(crate) fn pagination(args: &clap::ArgMatches) -> Pagination {
+  let value = |id: &str| args.try_get_one::<u32>(id).ok().flatten().map(|value| *value as usize);
+  Pagination {
+    page_size: value("page-size"),
+    limit: value("limit")
+  }
+}
+
+/// Returns the string value at the JSON pointer, or an empty string if there is no such value
+pub(crate) fn json_str<'a>(json: &'a Value, pointer: &str) -> &'a str {
+  json.pointer(pointer).and_then(|value| value.as_str()).unwrap_or_default()
+}
+
+/// Returns true if the `--output` argument selects JSON output
+pub fn is_json_output(args: &clap::ArgMatches) -> bool {
+  args.try_get_one::<String>("output").ok().flatten().map(|output| output == "json").unwrap_or(false)
+}
+
+/// Returns the name of the current branch of the git checkout in the working directory
+pub fn git_branch() -> anyhow::Result<String> {
+  let output = std::process::Command::new("git")
+    .args(["rev-parse", "--abbrev-ref", "HEAD"])
+    .output()
+    .map_err(|err| anyhow::anyhow!("Could not execute git to determine the current branch - {}", err))?;
+  if !output.status.success() {
+    return Err(anyhow::anyhow!("Could not determine the current git branch - {}",
+      String::from_utf8_lossy(&output.stderr).trim()));
+  }
+  let branch = String::from_utf8_lossy(&output.stdout).trim().to_string();
+  if branch.is_empty() || branch == "HEAD" {
+    Err(anyhow::anyhow!("Could not determine the current git branch, the git checkout is not on a branch"))
+  } else {
+    Ok(branch)
+  }
+}
+
+/// Returns the commit SHA of the git checkout in the working directory
+pub fn git_commit() -> anyhow::Result<String> {
+  let output = std::process::Command::new("git")
+    .args(["rev-parse", "HEAD"])
+    .output()
+    .map_err(|err| anyhow::anyhow!("Could not execute git to determine the current commit - {}", err))?;
+  if output.status.success() {
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+  } else {
+    Err(anyhow::anyhow!("Could not determine the current git commit - {}",
+      String::from_utf8_lossy(&output.stderr).trim()))
+  }
+}
+
+/// Environment variables that CI servers set to the commit being built, in order of precedence
+const COMMIT_ENV_VARS: [&str; 10] = ["GITHUB_SHA", "BUILDKITE_COMMIT", "CIRCLE_SHA1", "TRAVIS_COMMIT", "GIT_COMMIT",
+  "APPVEYOR_REPO_COMMIT", "CI_COMMIT_ID", "BITBUCKET_COMMIT", "BUILD_SOURCEVERSION", "CI_COMMIT_SHA"];
+
+/// Environment variables that CI servers set to the branch being built, in order of precedence
+const BRANCH_ENV_VARS: [&str; 13] = ["GITHUB_HEAD_REF", "GITHUB_REF", "BUILDKITE_BRANCH", "CIRCLE_BRANCH",
+  "TRAVIS_BRANCH", "GIT_BRANCH", "GIT_LOCAL_BRANCH", "APPVEYOR_REPO_BRANCH", "CI_COMMIT_REF_NAME",
+  "CI_BRANCH", "BITBUCKET_BRANCH", "BUILD_SOURCEBRANCHNAME", "CIRCLE_TAG"];
+
+fn env_var(name: &str) -> Option<String> {
+  std::env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+/// Returns the branch being built from the environment variables set by Buildkite, Circle CI,
+/// Travis CI, GitHub Actions, Jenkins, Hudson, AppVeyor, GitLab, CodeShip, Bitbucket or Azure DevOps
+pub fn ci_branch(env: impl Fn(&str) -> Option<String>) -> Option<String> {
+  BRANCH_ENV_VARS.iter()
+    .find_map(|name| env(name))
+    .map(|branch| branch.strip_prefix("refs/heads/").map(|branch| branch.to_string()).unwrap_or(branch))
+}
+
+/// Returns the commit being built from the environment variables set by the supported CI servers
+pub fn ci_commit(env: impl Fn(&str) -> Option<String>) -> Option<String> {
+  COMMIT_ENV_VARS.iter().find_map(|name| env(name))
+}
+
+/// Returns the URL of the build from the environment variables set by the supported CI servers
+pub fn ci_build_url(env: impl Fn(&str) -> Option<String>) -> Option<String> {
+  let all = |names: &[&str]| names.iter().map(|name| env(name)).collect::<Option<Vec<_>>>();
+  ["BUILDKITE_BUILD_URL", "CIRCLE_BUILD_URL", "TRAVIS_BUILD_WEB_URL", "BUILD_URL", "CI_JOB_URL", "CI_BUILD_URL"].iter()
+    .find_map(|name| env(name))
+    .or_else(|| all(&["GITHUB_SERVER_URL", "GITHUB_REPOSITORY", "GITHUB_RUN_ID"])
+      .map(|values| format!("{}/{}/actions/runs/{}", values[0], values[1], values[2])))
+    .or_else(|| all(&["APPVEYOR_URL", "APPVEYOR_ACCOUNT_NAME", "APPVEYOR_PROJECT_SLUG", "APPVEYOR_BUILD_ID"])
+      .map(|values| format!("{}/project/{}/{}/builds/{}", values[0], values[1], values[2], values[3])))
+    .or_else(|| all(&["BITBUCKET_GIT_HTTP_ORIGIN", "BITBUCKET_BUILD_NUMBER"])
+      .map(|values| format!("{}/addon/pipelines/home#!/results/{}", values[0], values[1])))
+    .or_else(|| all(&["SYSTEM_TEAMFOUNDATIONCOLLECTIONURI", "SYSTEM_TEAMPROJECT", "BUILD_BUILDID"])
+      .map(|values| format!("{}{}/_build/results?buildId={}", values[0], values[1], values[2])))
+}
+
+/// Returns the branch being built, from the CI environment variables or otherwise the git checkout
+/// in the working directory
+pub fn current_branch() -> anyhow::Result<String> {
+  match ci_branch(env_var) {
+    Some(branch) => Ok(branch),
+    None => git_branch()
+  }
+}
+
+/// Returns the commit being built, from the CI environment variables or otherwise the git checkout
+/// in the working directory
+pub fn current_commit() -> anyhow::Result<String> {
+  match ci_commit(env_var) {
+    Some(commit) => Ok(commit),
+    None => git_commit()
+  }
+}
+
+/// Returns the URL of the CI build, if the build is running on one of the supported CI servers
+pub fn current_build_url() -> Option<String> {
+  ci_build_url(env_var)
+}
+
+/// Prints the notices returned by the Pact Broker, coloured by their type. Debug notices are only
+/// displayed if verbose output is enabled.
+pub fn display_notices(response: &Value, verbose: bool) {
+  if let Some(notices) = response.get("notices").and_then(|notices| notices.as_array()) {
+    for notice in notices {
+      let text = notice.get("text").and_then(|text| text.as_str()).unwrap_or_default();
+      match notice.get("type").and_then(|t| t.as_str()).unwrap_or_default() {
+        "debug" => if verbose { println!("{}", text) },
+        "warning" => println!("{}", Yellow.paint(text)),
+        "error" => println!("{}", Red.paint(text)),
+        "success" => println!("{}", Green.paint(text)),
+        "prompt" => println!("{}", Cyan.paint(text)),
+        _ => println!("{}", text)
+      }
+    }
+  }
+}
+
+/// Policy for retrying requests to the Pact Broker. Requests are retried on connection errors,
+/// server errors and rate limiting (`429 Too Many Requests`), with an exponential backoff with
+/// jitter. A `Retry-After` header on `429` and `503` responses takes precedence over the backoff.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+  /// Maximum number of times to retry a request after the first attempt
+  pub max_retries: u8,
+  /// Delay before the first retry. It doubles for each retry after that.
+  pub backoff: Duration,
+  /// Maximum delay between attempts, unless the Pact Broker asks for a longer one with `Retry-After`
+  pub max_backoff: Duration
+}
+
+impl Default for RetryPolicy {
+  fn default() -> Self {
+    RetryPolicy {
+      max_retries: 3,
+      backoff: Duration::from_millis(200),
+      max_backoff: Duration::from_secs(30)
     }
-    Some(rb) => {
-      futures::stream::iter((1..=retries).step_by(1))
-        .fold((None::<Result<reqwest::Response, reqwest::Error>>, rb.try_clone()), |(response, request), attempt| {
-          async move {
-            match request {
-              Some(request_builder) => match response {
-                None => {
-                  let next = request_builder.try_clone();
-                  (Some(request_builder.send().await), next)
-                },
-                Some(response) => {
-                  trace!("with_retries: attempt {}/{} is {:?}", attempt, retries, response);
-                  match response {
-                    Ok(ref res) => if res.status().is_server_error() {
-                      match request_builder.try_clone() {
-                        None => (Some(response), None),
-                        Some(rb) => {
-                          sleep(Duration::from_millis(10_u64.pow(attempt as u32))).await;
-                          (Some(request_builder.send().await), Some(rb))
-                        }
-                      }
-                    } else {
-                      (Some(response), None)
-                    },
-                    Err(ref err) => if err.is_status() {
-                      if err.status().unwrap_or_default().is_server_error() {
-                        match request_builder.try_clone() {
-                          None => (Some(response), None),
-                          Some(rb) => {
-                            sleep(Duration::from_millis(10_u64.pow(attempt as u32))).await;
-                            (Some(request_builder.send().await), Some(rb))
-                          }
-                        }
-                      } else {
-                        (Some(response), None)
-                      }
-                    } else {
-                      (Some(response), None)
-                    }
-                  }
-                }
-              }
-              None => (response, None)
-            }
-          }
-        }).await.0.unwrap()
+  }
+}
+
+impl RetryPolicy {
+  /// Returns the delay before the retry. Half of the delay is random, so that clients that failed
+  /// together do not retry together.
+  pub fn delay(&self, retry: u32) -> Duration {
+    let delay = self.backoff
+      .saturating_mul(2_u32.saturating_pow(retry.saturating_sub(1)))
+      .min(self.max_backoff);
+    let half = delay / 2;
+    half + half.mul_f64(rand::random::<f64>())
+  }
+}
+
+/// Returns the delay requested with the `Retry-After` header, either in seconds or as an HTTP date
+fn retry_after(response: &reqwest::Response) -> Option<Duration> {
+  let value = response.headers().get(reqwest::header::RETRY_AFTER)?.to_str().ok()?.trim();
+  match value.parse::<u64>() {
+    Ok(seconds) => Some(Duration::from_secs(seconds)),
+    Err(_) => httpdate::parse_http_date(value).ok()
+      .map(|date| date.duration_since(SystemTime::now()).unwrap_or_default())
+  }
+}
+
+/// If the error is from the connection to the Pact Broker failing, being reset or closed before
+/// the response was received
+fn is_connection_error(err: &reqwest::Error) -> bool {
+  if err.is_connect() || err.is_timeout() || err.is_request() {
+    return true;
+  }
+  let mut source = err.source();
+  while let Some(cause) = source {
+    if let Some(io_error) = cause.downcast_ref::<std::io::Error>() {
+      return matches!(io_error.kind(), ErrorKind::ConnectionReset | ErrorKind::ConnectionAborted |
+        ErrorKind::BrokenPipe | ErrorKind::UnexpectedEof);
+    }
+    source = cause.source();
+  }
+  false
+}
+
+/// Determines if the result of the request should be retried. Returns the reason for the retry,
+/// and the delay requested by the Pact Broker if there was one. Requests that are not idempotent
+/// (like POST) may have been applied by the Pact Broker, so they are only retried if the connection
+/// could not be made, or the Pact Broker asked for the request to be retried with `Retry-After`.
+fn retry_reason(result: &Result<reqwest::Response, reqwest::Error>, idempotent: bool) -> Option<(String, Option<Duration>)> {
+  match result {
+    Ok(response) => {
+      let status = response.status();
+      if status == StatusCode::TOO_MANY_REQUESTS || status == StatusCode::SERVICE_UNAVAILABLE {
+        let retry_after = retry_after(response);
+        (idempotent || retry_after.is_some()).then(|| (status.to_string(), retry_after))
+      } else if status.is_server_error() && idempotent {
+        Some((status.to_string(), None))
+      } else {
+        None
+      }
+    }
+    Err(err) if err.is_connect() => Some((err.to_string(), None)),
+    Err(err) if idempotent && (err.status().is_some_and(|status| status.is_server_error()) || is_connection_error(err)) =>
+      Some((err.to_string(), None)),
+    Err(_) => None
+  }
+}
+
+/// Sends the request, retrying it with the retry policy on connection errors, server errors and
+/// rate limiting
+pub(crate) async fn with_retries(policy: &RetryPolicy, request: RequestBuilder) -> Result<reqwest::Response, reqwest::Error> {
+  let idempotent = request.try_clone()
+    .and_then(|request| request.build().ok())
+    .map(|request| request.method().is_idempotent())
+    .unwrap_or(false);
+  let mut request = request;
+  let mut retry = 0;
+  loop {
+    let next_request = if retry < policy.max_retries as u32 {
+      let next_request = request.try_clone();
+      if next_request.is_none() {
+        warn!("with_retries: Could not retry the request as it is not cloneable");
+      }
+      next_request
+    } else {
+      None
+    };
+    let result = request.send().await;
+    match (next_request, retry_reason(&result, idempotent)) {
+      (Some(next_request), Some((reason, retry_after))) => {
+        retry += 1;
+        let delay = retry_after.unwrap_or_else(|| policy.delay(retry));
+        let url = match &result {
+          Ok(response) => Some(response.url().to_string()),
+          Err(err) => err.url().map(|url| url.to_string())
+        };
+        warn!("Request to {} failed ({}), retrying in {} ms (retry {}/{})",
+          url.unwrap_or_default(), reason, delay.as_millis(), retry, policy.max_retries);
+        sleep(delay).await;
+        request = next_request;
+      }
+      (_, reason) => {
+        if let Some((reason, _)) = reason {
+          trace!("with_retries: giving up after {} retries - {}", retry, reason);
+        }
+        return result;
+      }
     }
   }
 }
@@ -76,3 +402,89 @@ pub(crate) fn as_safe_ref(interaction: &dyn Interaction) -> Box<dyn Interaction
     Box::new(v4)
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+
+  use expectest::prelude::*;
+
+  use super::*;
+  use super::super::stub_broker::{Route, StubBroker};
+
+  fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+    let vars: HashMap<String, String> = vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+    move |name| vars.get(name).cloned()
+  }
+
+  #[test]
+  fn ci_branch_uses_the_first_ci_variable_that_is_set() {
+    expect!(ci_branch(env(&[]))).to(be_none());
+    expect!(ci_branch(env(&[("GITHUB_REF", "refs/heads/feat/foo")]))).to(be_some().value("feat/foo"));
+    expect!(ci_branch(env(&[("GITHUB_REF", "refs/pull/1/merge"), ("GITHUB_HEAD_REF", "feat/bar")])))
+      .to(be_some().value("feat/bar"));
+    expect!(ci_branch(env(&[("CI_COMMIT_REF_NAME", "main")]))).to(be_some().value("main"));
+  }
+
+  #[test]
+  fn profile_credentials_are_not_sent_to_a_broker_url_from_the_arguments() {
+    let profile = BrokerProfile {
+      broker_base_url: Some("https://example.pactflow.io".to_string()),
+      broker_token: Some("xxxx".to_string()),
+      .. BrokerProfile::default()
+    };
+    let is_token = |auth: Option<HttpAuth>| matches!(auth, Some(HttpAuth::Token(token)) if token == "xxxx");
+    expect!(is_token(profile_auth(profile.clone(), false, false))).to(be_true());
+    expect!(profile_auth(profile.clone(), true, false).is_none()).to(be_true());
+    expect!(is_token(profile_auth(profile, true, true))).to(be_true());
+  }
+
+  #[tokio::test]
+  async fn with_retries_only_retries_post_requests_that_could_not_have_been_applied() {
+    let broker = StubBroker::start(vec![
+      Route::new("GET", "/pacticipants", 500),
+      Route::new("POST", "/pacticipants", 500),
+      Route::new("POST", "/webhooks", 503).with_header("Retry-After", "0")
+    ]);
+    let client = reqwest::Client::new();
+    let policy = RetryPolicy { max_retries: 1, backoff: Duration::from_millis(1), .. RetryPolicy::default() };
+
+    for request in [client.get(format!("{}/pacticipants", broker.url)), client.post(format!("{}/pacticipants", broker.url)),
+      client.post(format!("{}/webhooks", broker.url))] {
+      expect!(with_retries(&policy, request).await).to(be_ok());
+    }
+    expect!(broker.requests().iter().map(|request| format!("{} {}", request.method, request.path)).collect::<Vec<_>>())
+      .to(be_equal_to(vec![
+        "GET /pacticipants", "GET /pacticipants", "POST /pacticipants", "POST /webhooks", "POST /webhooks"
+      ]));
+  }
+
+  #[test]
+  fn ci_commit_uses_the_first_ci_variable_that_is_set() {
+    expect!(ci_commit(env(&[]))).to(be_none());
+    expect!(ci_commit(env(&[("CI_COMMIT_SHA", "abc123")]))).to(be_some().value("abc123"));
+    expect!(ci_commit(env(&[("CI_COMMIT_SHA", "abc123"), ("GITHUB_SHA", "def456")]))).to(be_some().value("def456"));
+  }
+
+  #[test]
+  fn retry_policy_delay_doubles_with_jitter_up_to_the_maximum() {
+    let policy = RetryPolicy {
+      max_retries: 5,
+      backoff: Duration::from_millis(100),
+      max_backoff: Duration::from_millis(300)
+    };
+    for (retry, min, max) in [(1, 50, 100), (2, 100, 200), (3, 150, 300), (10, 150, 300)] {
+      let delay = policy.delay(retry).as_millis();
+      expect!(delay >= min && delay <= max).to(be_true());
+    }
+  }
+
+  #[test]
+  fn ci_build_url_builds_the_url_for_github_actions() {
+    expect!(ci_build_url(env(&[]))).to(be_none());
+    expect!(ci_build_url(env(&[("BUILDKITE_BUILD_URL", "https://buildkite.com/org/pipeline/builds/1")])))
+      .to(be_some().value("https://buildkite.com/org/pipeline/builds/1"));
+    expect!(ci_build_url(env(&[("GITHUB_SERVER_URL", "https://github.com"), ("GITHUB_REPOSITORY", "pact-foundation/pact"),
+      ("GITHUB_RUN_ID", "123")]))).to(be_some().value("https://github.com/pact-foundation/pact/actions/runs/123"));
+  }
+}
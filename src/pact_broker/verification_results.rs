@@ -0,0 +1,207 @@
+//! Functions for publishing the results of a pact verification that was run without access to the
+//! Pact Broker, from the JSON report written by the verifier (`pact_verifier_cli --json`)
+
+use ansi_term::Colour::*;
+use clap::ArgMatches;
+use itertools::Itertools;
+use regex::Regex;
+use serde_json::Value;
+
+use crate::error::CliError;
+
+use super::{links_from_json, publish_verification_results_with_client, InteractionFailure, ProviderVersion, TestResult};
+use super::output::{print_output, OutputFormat, OutputOptions, ResourceTable, TableRow};
+use super::utils::{broker_client, json_str};
+
+/// Verification result published to the Pact Broker
+struct PublishedVerificationResult {
+  consumer: String,
+  provider: String,
+  provider_version: String,
+  success: bool,
+  url: String
+}
+
+impl TableRow for PublishedVerificationResult {
+  fn headers() -> Vec<&'static str> {
+    vec!["CONSUMER", "PROVIDER", "PROVIDER VERSION", "SUCCESS?", "URL"]
+  }
+
+  fn row(&self) -> Vec<String> {
+    vec![self.consumer.clone(), self.provider.clone(), self.provider_version.clone(), self.success.to_string(), self.url.clone()]
+  }
+}
+
+fn interaction_failures(report: &Value, key: &str) -> Vec<(Option<String>, Option<String>, Option<InteractionFailure>)> {
+  report.get(key)
+    .and_then(|errors| errors.as_array())
+    .cloned()
+    .unwrap_or_default()
+    .iter()
+    .map(|error| {
+      let id = error.get("interactionId").and_then(|id| id.as_str()).map(|id| id.to_string());
+      let description = error.get("interaction").and_then(|desc| desc.as_str()).map(|desc| desc.to_string());
+      let failure = match json_str(error, "/mismatch/type") {
+        "error" => InteractionFailure::Error(json_str(error, "/mismatch/message").to_string()),
+        _ => InteractionFailure::Mismatches(error.pointer("/mismatch/mismatches")
+          .and_then(|mismatches| mismatches.as_array())
+          .cloned()
+          .unwrap_or_default())
+      };
+      (id, description, Some(failure))
+    })
+    .collect()
+}
+
+/// Converts the JSON report from the verifier into the test result to publish. Failures of pending
+/// pacts are published as failures, as the Pact Broker uses them to determine the pending status.
+pub fn test_result_from_report(report: &Value) -> Result<TestResult, CliError> {
+  let success = report.get("result")
+    .and_then(|result| result.as_bool())
+    .ok_or_else(|| CliError::InvalidArguments(
+      "The verification report is not a JSON report from the Pact verifier, it has no 'result' attribute".to_string()))?;
+  let mut failures = interaction_failures(report, "errors");
+  failures.extend(interaction_failures(report, "pendingErrors"));
+  if success && failures.is_empty() {
+    Ok(TestResult::Ok(vec![]))
+  } else {
+    Ok(TestResult::Failed(failures))
+  }
+}
+
+/// Returns the consumer and provider names of the pacts verified in the report, from the
+/// "Verifying a pact between" lines of the verifier output
+pub fn verified_pacts(report: &Value) -> Vec<(String, String)> {
+  let ansi_codes = Regex::new("\x1b\\[[0-9;]*m").unwrap();
+  let pact_line = Regex::new(r"^Verifying a pact between (.+) and (.+?)(?: \[.*\]| \(.*\))?$").unwrap();
+  report.get("output")
+    .and_then(|output| output.as_array())
+    .cloned()
+    .unwrap_or_default()
+    .iter()
+    .filter_map(|output| output.as_str())
+    .flat_map(|output| output.lines().map(|line| ansi_codes.replace_all(line, "").trim().to_string()).collect_vec())
+    .filter_map(|line| pact_line.captures(&line).map(|names| (names[1].to_string(), names[2].to_string())))
+    .unique()
+    .collect()
+}
+
+/// Checks that the report only has the results of verifying the pact between the consumer and the
+/// provider. The failures in the report do not say which pact they belong to, so the results of
+/// verifying several pacts can not be published for one of them.
+fn check_report_is_for_pact(report: &Value, consumer: &str, provider: &str) -> Result<(), CliError> {
+  let other_pacts = verified_pacts(report).into_iter()
+    .filter(|(report_consumer, report_provider)| report_consumer != consumer || report_provider != provider)
+    .map(|(consumer, provider)| format!("the pact between {} and {}", consumer, provider))
+    .collect_vec();
+  if other_pacts.is_empty() {
+    Ok(())
+  } else {
+    Err(CliError::InvalidArguments(format!(
+      "The verification report also has the results of verifying {}. Please verify the pact between {} and {} on its own to publish its results",
+      other_pacts.join(", "), consumer, provider)))
+  }
+}
+
+fn read_report(path: &str) -> Result<Value, CliError> {
+  let contents = std::fs::read_to_string(path)
+    .map_err(|err| CliError::InvalidArguments(format!("Could not read the verification report '{}' - {}", path, err)))?;
+  serde_json::from_str(&contents)
+    .map_err(|err| CliError::InvalidArguments(format!("The verification report '{}' is not valid JSON - {}", path, err)))
+}
+
+/// Handles the `pact-broker publish-verification-results` command
+pub async fn publish_verification_results(args: &ArgMatches) -> Result<(), CliError> {
+  let report_path = args.get_one::<String>("REPORT").cloned().unwrap_or_default();
+  let pact_url = args.get_one::<String>("pact-url").cloned().unwrap_or_default();
+  let report = read_report(&report_path)?;
+  let result = test_result_from_report(&report)?;
+  let provider = ProviderVersion {
+    version: args.get_one::<String>("provider-app-version").cloned().unwrap_or_default(),
+    build_url: args.get_one::<String>("build-url").cloned(),
+    tags: args.get_many::<String>("tag").map(|tags| tags.cloned().collect()).unwrap_or_default(),
+    branch: args.get_one::<String>("branch").cloned()
+  };
+  let hal_client = broker_client(args)?;
+
+  let pact = hal_client.clone().fetch(pact_url.as_str()).await
+    .map_err(|err| CliError::broker("Failed to fetch the pact", err))?;
+  check_report_is_for_pact(&report, json_str(&pact, "/consumer/name"), json_str(&pact, "/provider/name"))?;
+
+  let links = links_from_json(&pact);
+  match publish_verification_results_with_client(&hal_client, &links, &result, &provider).await {
+    Ok(response) => {
+      let options = OutputOptions::from_args(args);
+      if options.format == OutputFormat::Table {
+        let outcome = if result.to_bool() { Green.paint("successful") } else { Red.paint("failed") };
+        println!("Published {} verification results of the pact between {} and {} for provider version {} to the Pact Broker.",
+          outcome, json_str(&pact, "/consumer/name"), json_str(&pact, "/provider/name"), provider.version);
+        Ok(())
+      } else {
+        let published = PublishedVerificationResult {
+          consumer: json_str(&pact, "/consumer/name").to_string(),
+          provider: json_str(&pact, "/provider/name").to_string(),
+          provider_version: provider.version.clone(),
+          success: result.to_bool(),
+          url: json_str(&response, "/_links/self/href").to_string()
+        };
+        print_output(&ResourceTable::single(&response, published), &options)
+      }
+    }
+    Err(err) => Err(CliError::broker("Failed to publish the verification results", err))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use super::*;
+
+  #[test]
+  fn test_result_from_report_includes_the_errors_and_pending_errors() {
+    let report = json!({
+      "result": false,
+      "output": [],
+      "errors": [{
+        "interaction": "a request for an order",
+        "mismatch": {
+          "type": "mismatches",
+          "mismatches": [{ "attribute": "status", "description": "expected 200 but was 404" }]
+        }
+      }],
+      "pendingErrors": [{
+        "interaction": "a request for a user",
+        "mismatch": { "type": "error", "message": "Connection refused" }
+      }]
+    });
+    expect!(test_result_from_report(&report).unwrap()).to(be_equal_to(TestResult::Failed(vec![
+      (None, Some("a request for an order".to_string()), Some(InteractionFailure::Mismatches(vec![
+        json!({ "attribute": "status", "description": "expected 200 but was 404" })
+      ]))),
+      (None, Some("a request for a user".to_string()), Some(InteractionFailure::Error("Connection refused".to_string())))
+    ])));
+    expect!(test_result_from_report(&json!({ "result": true, "errors": [] })).unwrap()).to(be_equal_to(TestResult::Ok(vec![])));
+    expect!(test_result_from_report(&json!({ "errors": [] }))).to(be_err());
+  }
+
+  #[test]
+  fn verified_pacts_reads_the_pacts_from_the_verifier_output() {
+    let report = json!({
+      "result": true,
+      "output": [
+        "\nVerifying a pact between \x1b[1mFoo\x1b[0m and \x1b[1mBar\x1b[0m",
+        "  a request for an order (0s loading, 200ms verification)",
+        "\nVerifying a pact between Baz and Bar [Pending]\n  a request for a user"
+      ]
+    });
+    expect!(verified_pacts(&report)).to(be_equal_to(vec![
+      ("Foo".to_string(), "Bar".to_string()),
+      ("Baz".to_string(), "Bar".to_string())
+    ]));
+    expect!(check_report_is_for_pact(&report, "Foo", "Bar").map_err(|err| err.to_string())).to(be_err().value(
+      "The verification report also has the results of verifying the pact between Baz and Bar. Please verify the pact between Foo and Bar on its own to publish its results".to_string()));
+    expect!(check_report_is_for_pact(&json!({ "output": [] }), "Foo", "Bar")).to(be_ok());
+  }
+}
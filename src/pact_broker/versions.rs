@@ -0,0 +1,198 @@
+//! Functions for managing pacticipant versions and their tags
+
+use clap::ArgMatches;
+use itertools::Itertools;
+use maplit::hashmap;
+use serde_json::{json, Value};
+
+use crate::error::CliError;
+
+use super::{HALClient, PactBrokerError};
+use super::deployments::fetch_pacticipant_version;
+use super::output::{print_output, OutputFormat, OutputOptions, ResourceTable, TableRow};
+use super::resources::{from_json, Version};
+use super::utils::{broker_client, current_branch};
+
+impl TableRow for Version {
+  fn headers() -> Vec<&'static str> {
+    vec!["VERSION", "BRANCHES", "TAGS"]
+  }
+
+  fn row(&self) -> Vec<String> {
+    vec![self.number.clone(), self.branch_names().join(", "), self.tag_names().join(", ")]
+  }
+}
+
+/// Returns the table of the pacticipant version
+pub fn version_table(version: &Value) -> Result<ResourceTable<'_, Version>, PactBrokerError> {
+  Ok(ResourceTable::single(version, from_json(version)?))
+}
+
+/// Returns the tags from the `--tag` arguments, adding the current branch if
+/// `--tag-with-git-branch` was given
+fn tags_from_args(args: &ArgMatches) -> anyhow::Result<Vec<String>> {
+  let mut tags = args.get_many::<String>("tag")
+    .map(|tags| tags.cloned().collect_vec())
+    .unwrap_or_default();
+  if args.get_flag("tag-with-git-branch") {
+    tags.push(current_branch()?);
+  }
+  Ok(tags.into_iter().unique().collect())
+}
+
+/// Handles the `pact-broker create-version-tag` command. Unless `--auto-create-version` is given,
+/// the version must already exist.
+pub async fn create_version_tag(args: &ArgMatches) -> Result<(), CliError> {
+  let pacticipant = args.get_one::<String>("pacticipant").cloned().unwrap_or_default();
+  let version = args.get_one::<String>("version").cloned().unwrap_or_default();
+  let tags = tags_from_args(args).map_err(CliError::from)?;
+  if tags.is_empty() {
+    return Err(CliError::InvalidArguments("Please specify a tag using --tag or --tag-with-git-branch".to_string()));
+  }
+  let hal_client = broker_client(args)?;
+
+  let result = async {
+    let hal_client = hal_client.with_index().await?;
+    if !args.get_flag("auto-create-version") {
+      fetch_pacticipant_version(&hal_client, &pacticipant, &version).await?;
+    }
+    for tag in &tags {
+      println!("Tagging {} version {} as {}", pacticipant, version, tag);
+      let url = hal_client.link_url("pb:pacticipant-version-tag", &hashmap!{
+        "pacticipant".to_string() => pacticipant.clone(),
+        "version".to_string() => version.clone(),
+        "tag".to_string() => tag.clone()
+      })?;
+      hal_client.put_json(url.as_str(), "{}").await?;
+    }
+    Ok::<_, PactBrokerError>(())
+  }.await;
+
+  result.map_err(CliError::from)
+}
+
+/// Fetches the version to describe: the given version number, the latest version with the tag
+/// given with `--latest`, or otherwise the latest version of the pacticipant
+async fn fetch_version_to_describe(
+  hal_client: &HALClient,
+  pacticipant: &str,
+  version: Option<&String>,
+  latest_tag: Option<&String>
+) -> Result<Value, PactBrokerError> {
+  let hal_client = match (version, latest_tag) {
+    (Some(version), _) => fetch_pacticipant_version(hal_client, pacticipant, version).await?,
+    (None, Some(tag)) if !tag.is_empty() => hal_client.clone()
+      .navigate("pb:latest-tagged-version", &hashmap!{
+        "pacticipant".to_string() => pacticipant.to_string(),
+        "tag".to_string() => tag.clone()
+      })
+      .await
+      .map_err(|err| match err {
+        PactBrokerError::NotFound(_) => PactBrokerError::NotFound(
+          format!("No version of pacticipant '{}' with tag '{}' was found", pacticipant, tag)),
+        _ => err
+      })?,
+    _ => hal_client.clone()
+      .navigate("pb:latest-version", &hashmap!{ "pacticipant".to_string() => pacticipant.to_string() })
+      .await
+      .map_err(|err| match err {
+        PactBrokerError::NotFound(_) => PactBrokerError::NotFound(
+          format!("No versions of pacticipant '{}' were found", pacticipant)),
+        _ => err
+      })?
+  };
+  Ok(hal_client.path_info.unwrap_or_default())
+}
+
+/// Handles the `pact-broker describe-version` command
+pub async fn describe_version(args: &ArgMatches) -> Result<(), CliError> {
+  let pacticipant = args.get_one::<String>("pacticipant").cloned().unwrap_or_default();
+  let hal_client = broker_client(args)?;
+
+  match fetch_version_to_describe(&hal_client, &pacticipant, args.get_one::<String>("version"),
+    args.get_one::<String>("latest")).await {
+    Ok(version) => print_output(&version_table(&version)?, &OutputOptions::from_args(args)),
+    Err(err) => Err(err.into())
+  }
+}
+
+/// Builds the version resource from the `--branch` and `--tag` arguments
+pub fn version_payload(args: &ArgMatches) -> Value {
+  let mut payload = json!({});
+  if let Some(branch) = args.get_one::<String>("branch") {
+    payload["branch"] = json!(branch);
+  }
+  if let Some(tags) = args.get_many::<String>("tag") {
+    payload["tags"] = json!(tags.unique().map(|tag| json!({ "name": tag })).collect_vec());
+  }
+  payload
+}
+
+/// Handles the `pact-broker create-or-update-version` command
+pub async fn create_or_update_version(args: &ArgMatches) -> Result<(), CliError> {
+  let pacticipant = args.get_one::<String>("pacticipant").cloned().unwrap_or_default();
+  let version = args.get_one::<String>("version").cloned().unwrap_or_default();
+  let payload = version_payload(args);
+  let hal_client = broker_client(args)?;
+
+  let result = async {
+    let hal_client = hal_client.with_index().await?;
+    let url = hal_client.link_url("pb:pacticipant-version", &hashmap!{
+      "pacticipant".to_string() => pacticipant.clone(),
+      "version".to_string() => version.clone()
+    })?;
+    let exists = match fetch_pacticipant_version(&hal_client, &pacticipant, &version).await {
+      Ok(_) => true,
+      Err(PactBrokerError::NotFound(_)) => false,
+      Err(err) => return Err(err)
+    };
+    hal_client.put_json(url.as_str(), payload.to_string().as_str()).await
+      .map(|response| (response, exists))
+  }.await;
+
+  match result {
+    Ok((response, exists)) => {
+      let options = OutputOptions::from_args(args);
+      if options.format == OutputFormat::Table {
+        println!("{} {} version {}", if exists { "Updated" } else { "Created" }, pacticipant, version);
+      }
+      print_output(&version_table(&response)?, &options)
+    }
+    Err(err) => Err(err.into())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use crate::cli::subcommand_args;
+  use crate::pact_broker::output::Tabular;
+
+  use super::*;
+
+  #[test]
+  fn version_payload_includes_the_branch_and_tags() {
+    let args = subcommand_args(&["pact-broker", "create-or-update-version",
+      "-b", "http://localhost", "-a", "Foo", "-e", "1", "--branch", "main", "-t", "dev", "-t", "prod", "-t", "dev"]);
+    expect!(version_payload(&args)).to(be_equal_to(json!({
+      "branch": "main",
+      "tags": [{ "name": "dev" }, { "name": "prod" }]
+    })));
+  }
+
+  #[test]
+  fn version_table_lists_the_branches_and_tags() {
+    let version = json!({
+      "number": "1.0.0",
+      "_embedded": {
+        "branchVersions": [{ "name": "main" }],
+        "tags": [{ "name": "dev" }, { "name": "prod" }]
+      }
+    });
+    expect!(version_table(&version).unwrap().rows()).to(be_equal_to(vec![
+      vec!["1.0.0".to_string(), "main".to_string(), "dev, prod".to_string()]
+    ]));
+  }
+}
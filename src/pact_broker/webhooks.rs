@@ -0,0 +1,378 @@
+//! Functions for managing webhooks using the `pb:webhooks` relation
+
+use std::fs;
+
+use ansi_term::Colour::*;
+use clap::ArgMatches;
+use itertools::Itertools;
+use maplit::hashmap;
+use serde_json::{json, Map, Value};
+
+use crate::error::CliError;
+
+use super::{HALClient, PactBrokerError};
+use super::resources::{from_json, Webhook};
+use super::utils::{broker_client, json_str};
+
+/// Webhook event flags, and the name of the event in the Pact Broker
+const WEBHOOK_EVENTS: [(&str, &str); 6] = [
+  ("contract-content-changed", "contract_content_changed"),
+  ("contract-published", "contract_published"),
+  ("provider-verification-published", "provider_verification_published"),
+  ("provider-verification-failed", "provider_verification_failed"),
+  ("provider-verification-succeeded", "provider_verification_succeeded"),
+  ("contract-requiring-verification-published", "contract_requiring_verification_published")
+];
+
+/// Parses a curl style header (`Name: value`)
+fn parse_header(header: &str) -> anyhow::Result<(String, String)> {
+  match header.split_once(':') {
+    Some((name, value)) if !name.trim().is_empty() => Ok((name.trim().to_string(), value.trim().to_string())),
+    _ => Err(anyhow::anyhow!("Invalid header '{}', expected the format 'Name: value'", header))
+  }
+}
+
+/// Returns the webhook body from the `--data` argument. As with curl, `@FILE` loads the body from
+/// the file. Bodies that are valid JSON are sent as JSON, otherwise they are sent as a string.
+fn webhook_body(data: &str) -> anyhow::Result<Value> {
+  let data = match data.strip_prefix('@') {
+    Some(path) => fs::read_to_string(path)
+      .map_err(|err| anyhow::anyhow!("Could not read the webhook body from '{}' - {}", path, err))?,
+    None => data.to_string()
+  };
+  Ok(serde_json::from_str(&data).unwrap_or(Value::String(data)))
+}
+
+fn pacticipant_selector(args: &ArgMatches, name_arg: &str, label_arg: &str) -> Option<Value> {
+  args.get_one::<String>(name_arg).map(|name| json!({ "name": name }))
+    .or_else(|| args.get_one::<String>(label_arg).map(|label| json!({ "label": label })))
+}
+
+/// Builds the webhook resource from the curl style command arguments. The pacticipant names and
+/// labels are mutually exclusive, which is enforced by the command line parser.
+pub fn webhook_payload(args: &ArgMatches) -> anyhow::Result<Value> {
+  let events = WEBHOOK_EVENTS.iter()
+    .filter(|(flag, _)| args.get_flag(flag))
+    .map(|(_, event)| json!({ "name": event }))
+    .collect::<Vec<_>>();
+  if events.is_empty() {
+    return Err(anyhow::anyhow!("You must specify at least one of {}",
+      WEBHOOK_EVENTS.iter().map(|(flag, _)| format!("--{}", flag)).join(", ")));
+  }
+
+  let mut request = json!({
+    "method": args.get_one::<String>("request").map(|method| method.to_uppercase()).unwrap_or_else(|| "POST".to_string()),
+    "url": args.get_one::<String>("url").cloned().unwrap_or_default()
+  });
+  if let Some(headers) = args.get_many::<String>("header") {
+    let mut header_map = Map::new();
+    for header in headers {
+      let (name, value) = parse_header(header)?;
+      header_map.insert(name, json!(value));
+    }
+    request["headers"] = Value::Object(header_map);
+  }
+  if let Some(data) = args.get_one::<String>("data") {
+    request["body"] = webhook_body(data)?;
+  }
+  if let Some(user) = args.get_one::<String>("user") {
+    match user.split_once(':') {
+      Some((username, password)) => {
+        request["username"] = json!(username);
+        request["password"] = json!(password);
+      }
+      None => request["username"] = json!(user)
+    }
+  }
+
+  let mut payload = json!({
+    "events": events,
+    "request": request
+  });
+  if let Some(description) = args.get_one::<String>("description") {
+    payload["description"] = json!(description);
+  }
+  if let Some(consumer) = pacticipant_selector(args, "consumer", "consumer-label") {
+    payload["consumer"] = consumer;
+  }
+  if let Some(provider) = pacticipant_selector(args, "provider", "provider-label") {
+    payload["provider"] = provider;
+  }
+  if let Some(team_uuid) = args.get_one::<String>("team-uuid") {
+    payload["teamUuid"] = json!(team_uuid);
+  }
+  Ok(payload)
+}
+
+fn display_webhook(webhook: &Value, action: &str) {
+  let webhook: Webhook = from_json(webhook).unwrap_or_default();
+  let uuid = webhook.webhook_uuid().unwrap_or_default();
+  match webhook.description.as_deref() {
+    Some(description) if !description.is_empty() =>
+      println!("{}", Green.paint(format!("Webhook \"{}\" {} with UUID {}", description, action, uuid))),
+    _ => println!("{}", Green.paint(format!("Webhook {} with UUID {}", action, uuid)))
+  }
+  let relations = webhook.links.relations();
+  if !relations.is_empty() {
+    println!("Links:");
+    for relation in relations {
+      for link in webhook.links.links(relation) {
+        println!("  {}: {}", relation, link.href.as_deref().unwrap_or_default());
+      }
+    }
+  }
+}
+
+/// Handles the `pact-broker create-webhook` command
+pub async fn create_webhook(args: &ArgMatches) -> Result<(), CliError> {
+  let payload = webhook_payload(args).map_err(CliError::from)?;
+  let hal_client = broker_client(args)?;
+
+  let result = async {
+    let hal_client = hal_client.with_index().await?;
+    let url = hal_client.link_url("pb:webhooks", &hashmap!{})?;
+    hal_client.post_json(url.as_str(), payload.to_string().as_str()).await
+  }.await;
+
+  match result {
+    Ok(webhook) => {
+      display_webhook(&webhook, "created");
+      Ok(())
+    }
+    Err(err) => Err(err.into())
+  }
+}
+
+/// Returns the URL of the webhook with the given UUID, using the `pb:webhook` relation if the index
+/// resource has one
+fn webhook_url(hal_client: &HALClient, uuid: &str) -> Result<String, PactBrokerError> {
+  hal_client.link_url("pb:webhook", &hashmap!{ "uuid".to_string() => uuid.to_string() })
+    .or_else(|_| hal_client.link_url("pb:webhooks", &hashmap!{})
+      .map(|url| format!("{}/{}", url.trim_end_matches('/'), urlencoding::encode(uuid))))
+}
+
+/// Handles the `pact-broker create-or-update-webhook` command. The webhook is PUT to the URL for the
+/// UUID, which creates it if it does not already exist.
+pub async fn create_or_update_webhook(args: &ArgMatches) -> Result<(), CliError> {
+  let uuid = args.get_one::<String>("uuid").cloned().unwrap_or_default();
+  let payload = webhook_payload(args).map_err(CliError::from)?;
+  let hal_client = broker_client(args)?;
+
+  let result = async {
+    let hal_client = hal_client.with_index().await?;
+    let url = webhook_url(&hal_client, &uuid)?;
+    let exists = match hal_client.clone().fetch(url.as_str()).await {
+      Ok(_) => true,
+      Err(PactBrokerError::NotFound(_)) => false,
+      Err(err) => return Err(err)
+    };
+    hal_client.put_json(url.as_str(), payload.to_string().as_str()).await
+      .map(|webhook| (webhook, exists))
+  }.await;
+
+  match result {
+    Ok((webhook, exists)) => {
+      display_webhook(&webhook, if exists { "updated" } else { "created" });
+      Ok(())
+    }
+    Err(err) => Err(err.into())
+  }
+}
+
+fn indent(text: &str, prefix: &str) -> String {
+  text.lines().map(|line| format!("{}{}", prefix, line)).join("\n")
+}
+
+fn format_http_details(details: &Value, lines: &mut Vec<String>) {
+  if let Some(headers) = details.get("headers").and_then(|headers| headers.as_object()) {
+    if !headers.is_empty() {
+      lines.push("  Headers:".to_string());
+      for (name, value) in headers {
+        let value = match value {
+          Value::String(value) => value.clone(),
+          Value::Array(values) => values.iter().map(|value| value.as_str().map(|v| v.to_string())
+            .unwrap_or_else(|| value.to_string())).join(", "),
+          _ => value.to_string()
+        };
+        lines.push(format!("    {}: {}", name, value));
+      }
+    }
+  }
+  match details.get("body") {
+    None | Some(Value::Null) => {}
+    Some(Value::String(body)) => if !body.is_empty() {
+      lines.push("  Body:".to_string());
+      lines.push(indent(body, "    "));
+    },
+    Some(body) => {
+      lines.push("  Body:".to_string());
+      lines.push(indent(&serde_json::to_string_pretty(body).unwrap_or_default(), "    "));
+    }
+  }
+}
+
+/// Formats the webhook execution result returned by the Pact Broker: the rendered request, the
+/// response status, headers and body, and the execution logs
+pub fn format_webhook_execution(result: &Value) -> String {
+  let mut lines = vec![];
+  if let Some(request) = result.get("request").filter(|request| request.is_object()) {
+    lines.push("Request:".to_string());
+    let request_line = [json_str(request, "/method"), json_str(request, "/url")].iter()
+      .filter(|part| !part.is_empty())
+      .join(" ");
+    if !request_line.is_empty() {
+      lines.push(format!("  {}", request_line));
+    }
+    format_http_details(request, &mut lines);
+  }
+  if let Some(response) = result.get("response").filter(|response| response.is_object()) {
+    lines.push("Response:".to_string());
+    if let Some(status) = response.get("status") {
+      lines.push(format!("  Status: {}", status));
+    }
+    format_http_details(response, &mut lines);
+  }
+  let logs = json_str(result, "/logs");
+  if !logs.is_empty() {
+    lines.push("Logs:".to_string());
+    lines.push(indent(logs, "  "));
+  }
+  lines.join("\n")
+}
+
+/// If the webhook execution was successful. The `success` attribute is used if present, otherwise
+/// the status codes of the execution and the downstream response are checked.
+fn webhook_execution_succeeded(status: u16, result: &Value) -> bool {
+  match result.get("success").and_then(|success| success.as_bool()) {
+    Some(success) => success,
+    None => (200..300).contains(&status) && result.pointer("/response/status")
+      .and_then(|status| status.as_u64())
+      .map(|status| (200..300).contains(&status))
+      .unwrap_or(false)
+  }
+}
+
+/// Handles the `pact-broker test-webhook` command. This executes the webhook using the `pb:execute`
+/// relation and displays the logs from the execution. It fails if the downstream call failed.
+pub async fn test_webhook(args: &ArgMatches) -> Result<(), CliError> {
+  let uuid = args.get_one::<String>("uuid").cloned().unwrap_or_default();
+  let hal_client = broker_client(args)?;
+
+  let result = async {
+    let hal_client = hal_client.with_index().await?;
+    let url = webhook_url(&hal_client, &uuid)?;
+    let webhook = hal_client.clone().fetch(url.as_str()).await
+      .map_err(|err| match err {
+        PactBrokerError::NotFound(_) => PactBrokerError::NotFound(format!("No webhook with UUID '{}' was found", uuid)),
+        _ => err
+      })?;
+    let execute_url = match json_str(&webhook, "/_links/pb:execute/href") {
+      "" => format!("{}/execute", url.trim_end_matches('/')),
+      href => href.to_string()
+    };
+    hal_client.post_json_with_status(execute_url.as_str(), "{}").await
+  }.await;
+
+  match result {
+    Ok((status, execution)) => {
+      println!("{}", format_webhook_execution(&execution));
+      let message = json_str(&execution, "/message");
+      if webhook_execution_succeeded(status, &execution) {
+        println!("{}", Green.paint(if message.is_empty() { "Webhook executed successfully" } else { message }));
+        Ok(())
+      } else {
+        Err(CliError::Failed(if message.is_empty() { "Webhook execution failed" } else { message }.to_string()))
+      }
+    }
+    Err(err) => Err(err.into())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use crate::cli::subcommand_args;
+
+  use super::*;
+
+  #[test]
+  fn webhook_payload_is_built_from_the_curl_style_arguments() {
+    let args = subcommand_args(&["pact-broker", "create-webhook", "http://example.org/hook", "-b", "http://localhost",
+      "-X", "post", "-H", "Content-Type: application/json", "-H", "X-Token:abc",
+      "-d", "{\"version\": \"${pactbroker.consumerVersionNumber}\"}", "--user", "user:pa:ss", "--consumer", "Foo",
+      "--provider-label", "backend", "--description", "Trigger build", "--contract-published",
+      "--provider-verification-failed", "--team-uuid", "1234"]);
+    expect!(webhook_payload(&args).unwrap()).to(be_equal_to(json!({
+      "description": "Trigger build",
+      "consumer": { "name": "Foo" },
+      "provider": { "label": "backend" },
+      "events": [{ "name": "contract_published" }, { "name": "provider_verification_failed" }],
+      "request": {
+        "method": "POST",
+        "url": "http://example.org/hook",
+        "headers": { "Content-Type": "application/json", "X-Token": "abc" },
+        "body": { "version": "${pactbroker.consumerVersionNumber}" },
+        "username": "user",
+        "password": "pa:ss"
+      },
+      "teamUuid": "1234"
+    })));
+  }
+
+  #[test]
+  fn webhook_payload_requires_an_event() {
+    let args = subcommand_args(&["pact-broker", "create-webhook", "http://example.org/hook", "-b", "http://localhost", "--consumer", "Foo"]);
+    expect!(webhook_payload(&args).map_err(|err| err.to_string().starts_with("You must specify at least one of")))
+      .to(be_err().value(true));
+  }
+
+  #[test]
+  fn webhook_payload_rejects_invalid_headers() {
+    let args = subcommand_args(&["pact-broker", "create-webhook", "http://example.org/hook", "-b", "http://localhost",
+      "--contract-published", "-H", "no colon"]);
+    expect!(webhook_payload(&args).map_err(|err| err.to_string()))
+      .to(be_err().value("Invalid header 'no colon', expected the format 'Name: value'".to_string()));
+  }
+
+  #[test]
+  fn format_webhook_execution_displays_the_request_response_and_logs() {
+    let result = json!({
+      "request": {
+        "method": "POST",
+        "url": "http://ci/build",
+        "headers": { "Content-Type": "application/json" },
+        "body": { "branch": "main" }
+      },
+      "response": {
+        "status": 500,
+        "headers": { "Content-Type": ["text/plain"] },
+        "body": "Internal error"
+      },
+      "logs": "[2024-01-01] Executing webhook\n[2024-01-01] Response status 500",
+      "success": false
+    });
+    expect!(format_webhook_execution(&result)).to(be_equal_to(vec![
+      "Request:",
+      "  POST http://ci/build",
+      "  Headers:",
+      "    Content-Type: application/json",
+      "  Body:",
+      "    {",
+      "      \"branch\": \"main\"",
+      "    }",
+      "Response:",
+      "  Status: 500",
+      "  Headers:",
+      "    Content-Type: text/plain",
+      "  Body:",
+      "    Internal error",
+      "Logs:",
+      "  [2024-01-01] Executing webhook",
+      "  [2024-01-01] Response status 500"
+    ].join("\n")));
+    expect!(webhook_execution_succeeded(500, &result)).to(be_false());
+    expect!(webhook_execution_succeeded(200, &json!({ "response": { "status": 201 } }))).to(be_true());
+  }
+}